@@ -0,0 +1,444 @@
+use crate::{Barcode, Error, Result};
+use std::fmt::Display;
+
+/// Why barcode content doesn't fit its symbology.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BarcodeError {
+    /// The symbology can't encode the character at `position`, counted in characters from 0.
+    InvalidCharacter { position: usize, character: char },
+    /// The number of characters is not accepted.
+    InvalidLength {
+        length: usize,
+        expected: &'static str,
+    },
+    /// The content includes a check digit which doesn't match the data.
+    CheckDigit { expected: char, found: char },
+}
+
+impl Display for BarcodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidCharacter {
+                position,
+                character,
+            } => write!(
+                f,
+                "character {character:?} at position {position} is not allowed"
+            ),
+            Self::InvalidLength { length, expected } => {
+                write!(f, "{length} characters, expected {expected}")
+            }
+            Self::CheckDigit { expected, found } => {
+                write!(f, "check digit {found} doesn't match, expected {expected}")
+            }
+        }
+    }
+}
+
+/// Characters accepted by a symbology.
+#[derive(Clone, Copy)]
+enum Charset {
+    Digits,
+    /// Code 39 standard set. Full ASCII mode is left out, not every firmware switches to it.
+    Code39,
+    Codabar,
+    Code11,
+    Hex,
+    Ascii,
+}
+
+impl Charset {
+    fn contains(self, c: char) -> bool {
+        match self {
+            Self::Digits => c.is_ascii_digit(),
+            Self::Code39 => c.is_ascii_digit() || c.is_ascii_uppercase() || " -.$/+%".contains(c),
+            Self::Codabar => c.is_ascii_digit() || "-$:/.+".contains(c),
+            Self::Code11 => c.is_ascii_digit() || c == '-',
+            Self::Hex => c.is_ascii_digit() || ('A'..='F').contains(&c),
+            Self::Ascii => c.is_ascii(),
+        }
+    }
+}
+
+/// Check digit of the GS1 symbologies: digits weighted 3 and 1 alternately from the right.
+fn gs1_check_digit(data: &str) -> char {
+    let sum: u32 = data
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(n, b)| (b - b'0') as u32 * if n % 2 == 0 { 3 } else { 1 })
+        .sum();
+    char::from(b'0' + ((10 - sum % 10) % 10) as u8)
+}
+
+/// MSI modulo 10: every other digit from the right doubled, digits of the products summed.
+fn msi_check_digit(data: &str) -> char {
+    let sum: u32 = data
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(n, b)| {
+            let digit = (b - b'0') as u32;
+            match n % 2 {
+                0 => digit * 2 / 10 + digit * 2 % 10,
+                _ => digit,
+            }
+        })
+        .sum();
+    char::from(b'0' + ((10 - sum % 10) % 10) as u8)
+}
+
+/// Expands the 6 digits of a number system 0 UPC-E code to the 11 data digits of UPC-A.
+fn upce_to_upca(data: &str) -> String {
+    let d = data.as_bytes();
+    let s = |range: std::ops::Range<usize>| &data[range];
+    match d[5] {
+        b'0'..=b'2' => format!("0{}{}0000{}", s(0..2), s(5..6), s(2..5)),
+        b'3' => format!("0{}00000{}", s(0..3), s(3..5)),
+        b'4' => format!("0{}00000{}", s(0..4), s(4..5)),
+        _ => format!("0{}0000{}", s(0..5), s(5..6)),
+    }
+}
+
+impl Barcode {
+    /// The symbology without add-on and the number of add-on digits.
+    fn main_symbol(self) -> (Self, usize) {
+        match self {
+            Self::BarcodeEan13Plus2 => (Self::BarcodeEan13, 2),
+            Self::BarcodeEan13Plus5 => (Self::BarcodeEan13, 5),
+            Self::BarcodeEan8Plus2 => (Self::BarcodeEan8, 2),
+            Self::BarcodeEan8Plus5 => (Self::BarcodeEan8, 5),
+            Self::BarcodeUpcaPlus2 => (Self::BarcodeUpca, 2),
+            Self::BarcodeUpaPlus5 => (Self::BarcodeUpca, 5),
+            Self::BarcodeUpcePlus2 => (Self::BarcodeUpce, 2),
+            Self::BarcodeUpePlus5 => (Self::BarcodeUpce, 5),
+            other => (other, 0),
+        }
+    }
+
+    /// Number of data digits of symbologies with a fixed length and a check digit the printer adds.
+    fn data_digits(self) -> Option<usize> {
+        match self {
+            Self::BarcodeEan13 => Some(12),
+            Self::BarcodeEan8 => Some(7),
+            Self::BarcodeUpca => Some(11),
+            Self::BarcodeUpce => Some(6),
+            Self::BarcodeItf14 | Self::BarcodeEan14 => Some(13),
+            _ => None,
+        }
+    }
+
+    fn charset(self) -> Charset {
+        match self {
+            Self::Barcode128 | Self::Barcode128M | Self::BarcodeEan128 | Self::BarcodeEan128M => {
+                Charset::Ascii
+            }
+            Self::Barcode39 | Self::Barcode39C | Self::BarcodeLogmars => Charset::Code39,
+            Self::Barcode93 | Self::BarcodeTelepen | Self::BarcodeCode49 => Charset::Ascii,
+            Self::BarcodeCoda => Charset::Codabar,
+            Self::Barcode11 => Charset::Code11,
+            Self::BarcodePlessey => Charset::Hex,
+            _ => Charset::Digits,
+        }
+    }
+
+    /// Computes the check digit of `data` for EAN-8, EAN-13, UPC-A, UPC-E (6 digits, number system 0),
+    /// ITF-14, EAN-14 and MSI (modulo 10). Add-on digits are not part of `data`.
+    /// Returns `None` for other symbologies, or if `data` is not the symbology's digits without check digit.
+    pub fn check_digit(&self, data: &str) -> Option<char> {
+        if data.is_empty() || !data.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let (main, _) = self.main_symbol();
+        match main {
+            Self::BarcodeMsi | Self::BarcodeMsic => Some(msi_check_digit(data)),
+            Self::BarcodeUpce if data.len() == 6 => Some(gs1_check_digit(&upce_to_upca(data))),
+            Self::BarcodeUpce => None,
+            _ if main.data_digits() == Some(data.len()) => Some(gs1_check_digit(data)),
+            _ => None,
+        }
+    }
+
+    /// Checks `content` against the character set and length of the symbology and returns it
+    /// the way the printer expects it.
+    ///
+    /// EAN, UPC, ITF-14 and EAN-14 content may include the check digit, which is verified and then
+    /// left out since the printer adds it. UPC-E takes 6 digits, or 8 with number system 0 and check digit.
+    pub fn validate(&self, content: &str) -> Result<String> {
+        let invalid = |error| Error::InvalidBarcode {
+            symbology: *self,
+            error,
+        };
+        let length = content.chars().count();
+
+        let charset = self.charset();
+        let last = length.saturating_sub(1);
+        for (position, character) in content.chars().enumerate() {
+            // Codabar start and stop characters.
+            let stop = matches!(self, Self::BarcodeCoda)
+                && (position == 0 || position == last)
+                && ('A'..='D').contains(&character);
+            if !(charset.contains(character) || stop) {
+                return Err(invalid(BarcodeError::InvalidCharacter {
+                    position,
+                    character,
+                }));
+            }
+        }
+
+        let (main, add_on) = self.main_symbol();
+        let expected = match (main, add_on) {
+            (Self::BarcodeEan13, 0) => Some((&[12, 13][..], "12 or 13 digits")),
+            (Self::BarcodeEan13, 2) => Some((&[14, 15][..], "12 or 13 digits and 2 add-on digits")),
+            (Self::BarcodeEan13, _) => Some((&[17, 18][..], "12 or 13 digits and 5 add-on digits")),
+            (Self::BarcodeEan8, 0) => Some((&[7, 8][..], "7 or 8 digits")),
+            (Self::BarcodeEan8, 2) => Some((&[9, 10][..], "7 or 8 digits and 2 add-on digits")),
+            (Self::BarcodeEan8, _) => Some((&[12, 13][..], "7 or 8 digits and 5 add-on digits")),
+            (Self::BarcodeUpca, 0) => Some((&[11, 12][..], "11 or 12 digits")),
+            (Self::BarcodeUpca, 2) => Some((&[13, 14][..], "11 or 12 digits and 2 add-on digits")),
+            (Self::BarcodeUpca, _) => Some((&[16, 17][..], "11 or 12 digits and 5 add-on digits")),
+            (Self::BarcodeUpce, 0) => Some((&[6, 8][..], "6 or 8 digits")),
+            (Self::BarcodeUpce, 2) => Some((&[8, 10][..], "6 or 8 digits and 2 add-on digits")),
+            (Self::BarcodeUpce, _) => Some((&[11, 13][..], "6 or 8 digits and 5 add-on digits")),
+            (Self::BarcodeItf14 | Self::BarcodeEan14, _) => {
+                Some((&[13, 14][..], "13 or 14 digits"))
+            }
+            (Self::BarcodePost, _) => Some((&[5, 9, 11][..], "5, 9 or 11 digits")),
+            (Self::BarcodePlanet, _) => Some((&[11, 13][..], "11 or 13 digits")),
+            (Self::BarcodeDpi, _) => Some((&[11][..], "11 digits")),
+            (Self::BarcodeDpl, _) => Some((&[13][..], "13 digits")),
+            _ => None,
+        };
+        match expected {
+            Some((lengths, expected)) if !lengths.contains(&length) => {
+                return Err(invalid(BarcodeError::InvalidLength { length, expected }))
+            }
+            None if length == 0 => {
+                return Err(invalid(BarcodeError::InvalidLength {
+                    length,
+                    expected: "at least 1 character",
+                }))
+            }
+            _ => {}
+        }
+
+        let Some(data_digits) = main.data_digits() else {
+            return Ok(content.to_owned());
+        };
+        // Only digits are left at this point, byte and character positions are the same.
+        let (symbol, add_on) = content.split_at(length - add_on);
+        let data = match (main, symbol.len()) {
+            (Self::BarcodeUpce, 8) if !symbol.starts_with('0') => {
+                return Err(invalid(BarcodeError::InvalidCharacter {
+                    position: 0,
+                    character: symbol.as_bytes()[0] as char,
+                }))
+            }
+            (Self::BarcodeUpce, 8) => &symbol[1..7],
+            (_, len) if len > data_digits => &symbol[..data_digits],
+            _ => symbol,
+        };
+        if symbol.len() > data.len() {
+            let expected = main
+                .check_digit(data)
+                .expect("check digit of validated digits");
+            let found = symbol.as_bytes()[symbol.len() - 1] as char;
+            if found != expected {
+                return Err(invalid(BarcodeError::CheckDigit { expected, found }));
+            }
+        }
+        Ok(format!("{data}{add_on}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(barcode: Barcode, content: &str) -> BarcodeError {
+        match barcode.validate(content) {
+            Err(Error::InvalidBarcode { error, .. }) => error,
+            other => panic!("{barcode} {content}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn check_digits() {
+        let vectors = [
+            (Barcode::BarcodeEan13, "400638133393", '1'),
+            (Barcode::BarcodeEan13, "590123412345", '7'),
+            (Barcode::BarcodeEan8, "9638507", '4'),
+            (Barcode::BarcodeEan8, "5512345", '7'),
+            (Barcode::BarcodeUpca, "03600029145", '2'),
+            (Barcode::BarcodeUpce, "654321", '7'),
+            (Barcode::BarcodeItf14, "0001234560001", '2'),
+            (Barcode::BarcodeEan14, "1540014128876", '3'),
+            (Barcode::BarcodeMsi, "1234567", '4'),
+            (Barcode::BarcodeMsic, "80523", '4'),
+            (Barcode::BarcodeEan13Plus5, "590123412345", '7'),
+            (Barcode::BarcodeUpePlus5, "654321", '7'),
+        ];
+        for (barcode, data, digit) in vectors {
+            assert_eq!(barcode.check_digit(data), Some(digit), "{barcode} {data}");
+        }
+
+        assert_eq!(Barcode::BarcodeEan13.check_digit("4006381333931"), None);
+        assert_eq!(Barcode::BarcodeEan8.check_digit("96385A7"), None);
+        assert_eq!(Barcode::BarcodeUpce.check_digit("06543217"), None);
+        assert_eq!(Barcode::BarcodeMsi.check_digit(""), None);
+        assert_eq!(Barcode::Barcode128.check_digit("1234"), None);
+    }
+
+    #[test]
+    fn upce_expansion() {
+        // The last digit tells where the zeros of the UPC-A number were.
+        assert_eq!(upce_to_upca("654321"), "06510000432");
+        assert_eq!(upce_to_upca("123452"), "01220000345");
+        assert_eq!(upce_to_upca("123453"), "01230000045");
+        assert_eq!(upce_to_upca("123454"), "01234000005");
+        assert_eq!(upce_to_upca("123457"), "01234500007");
+        for (data, digit) in [
+            ("123452", '3'),
+            ("123453", '1'),
+            ("123454", '3'),
+            ("123457", '2'),
+        ] {
+            assert_eq!(
+                Barcode::BarcodeUpce.check_digit(data),
+                Some(digit),
+                "{data}"
+            );
+        }
+    }
+
+    #[test]
+    fn validation() {
+        let valid = [
+            (Barcode::BarcodeEan13, "4006381333931", "400638133393"),
+            (Barcode::BarcodeEan13, "400638133393", "400638133393"),
+            (Barcode::BarcodeEan8, "96385074", "9638507"),
+            (Barcode::BarcodeUpca, "036000291452", "03600029145"),
+            (Barcode::BarcodeUpce, "06543217", "654321"),
+            (Barcode::BarcodeUpce, "654321", "654321"),
+            (Barcode::BarcodeItf14, "00012345600012", "0001234560001"),
+            (Barcode::BarcodeEan14, "15400141288763", "1540014128876"),
+            (Barcode::BarcodeCoda, "A40156B", "A40156B"),
+            (Barcode::Barcode39, "CODE-39 $", "CODE-39 $"),
+        ];
+        for (barcode, content, sent) in valid {
+            assert_eq!(
+                barcode.validate(content).unwrap(),
+                sent,
+                "{barcode} {content}"
+            );
+        }
+
+        assert_eq!(
+            error(Barcode::BarcodeEan13, "5901234123458"),
+            BarcodeError::CheckDigit {
+                expected: '7',
+                found: '8'
+            }
+        );
+        assert_eq!(
+            error(Barcode::BarcodeUpce, "16543217"),
+            BarcodeError::InvalidCharacter {
+                position: 0,
+                character: '1'
+            }
+        );
+        assert_eq!(
+            error(Barcode::BarcodeEan8, "9638 074"),
+            BarcodeError::InvalidCharacter {
+                position: 4,
+                character: ' '
+            }
+        );
+        assert_eq!(
+            error(Barcode::Barcode39, "code39"),
+            BarcodeError::InvalidCharacter {
+                position: 0,
+                character: 'c'
+            }
+        );
+        assert_eq!(
+            error(Barcode::BarcodeCoda, "4A0156"),
+            BarcodeError::InvalidCharacter {
+                position: 1,
+                character: 'A'
+            }
+        );
+        assert!(matches!(
+            error(Barcode::BarcodeUpca, "0360002914"),
+            BarcodeError::InvalidLength { length: 10, .. }
+        ));
+        assert!(matches!(
+            error(Barcode::Barcode128, ""),
+            BarcodeError::InvalidLength { length: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn add_ons() {
+        // The check digit is dropped, the add-on digits are kept.
+        let valid = [
+            (
+                Barcode::BarcodeEan13Plus2,
+                "590123412345712",
+                "59012341234512",
+            ),
+            (
+                Barcode::BarcodeEan13Plus2,
+                "59012341234512",
+                "59012341234512",
+            ),
+            (
+                Barcode::BarcodeEan13Plus5,
+                "590123412345712345",
+                "59012341234512345",
+            ),
+            (Barcode::BarcodeEan8Plus2, "9638507412", "963850712"),
+            (Barcode::BarcodeEan8Plus5, "963850712345", "963850712345"),
+            (Barcode::BarcodeUpcaPlus2, "03600029145212", "0360002914512"),
+            (
+                Barcode::BarcodeUpaPlus5,
+                "03600029145212345",
+                "0360002914512345",
+            ),
+            (Barcode::BarcodeUpcePlus2, "0654321712", "65432112"),
+            (Barcode::BarcodeUpePlus5, "65432112345", "65432112345"),
+        ];
+        for (barcode, content, sent) in valid {
+            assert_eq!(
+                barcode.validate(content).unwrap(),
+                sent,
+                "{barcode} {content}"
+            );
+        }
+
+        // A check digit in place of an add-on digit.
+        assert_eq!(
+            error(Barcode::BarcodeEan13Plus2, "590123412345812"),
+            BarcodeError::CheckDigit {
+                expected: '7',
+                found: '8'
+            }
+        );
+        for (barcode, content) in [
+            (Barcode::BarcodeEan13Plus2, "5901234123457"),
+            (Barcode::BarcodeEan13Plus5, "5901234123457123"),
+            (Barcode::BarcodeEan8Plus5, "96385074123"),
+            (Barcode::BarcodeUpcePlus2, "065432171"),
+        ] {
+            assert!(
+                matches!(error(barcode, content), BarcodeError::InvalidLength { .. }),
+                "{barcode} {content}"
+            );
+        }
+        assert_eq!(
+            error(Barcode::BarcodeEan8Plus2, "9638507").to_string(),
+            "7 characters, expected 7 or 8 digits and 2 add-on digits"
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{Error, Label, Printer, Result, Template};
+use log::{debug, warn};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
+
+/// Settings of a batch print from CSV data.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Index of the first data row to print, not counting the header.
+    /// Pass [`BatchReport::next_row`] or the row of [`Error::Row`] here to resume after a failure.
+    pub start_row: usize,
+    /// Copies printed for every row.
+    pub copies: u32,
+    /// Skip rows which can't be rendered or drawn instead of stopping at the first one.
+    pub skip_invalid_rows: bool,
+    /// CSV column to placeholder mapping. Columns not listed fill the placeholder named after their header.
+    pub columns: HashMap<String, String>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            start_row: 0,
+            copies: 1,
+            skip_invalid_rows: false,
+            columns: HashMap::new(),
+        }
+    }
+}
+
+/// A row which was skipped because it couldn't be rendered or drawn.
+#[derive(Debug)]
+pub struct RowError {
+    pub row: usize,
+    pub error: Error,
+}
+
+/// Outcome of a batch print.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// Number of labels sent to the printer.
+    pub printed: usize,
+    /// Rows skipped with [`BatchOptions::skip_invalid_rows`].
+    pub skipped: Vec<RowError>,
+    /// Index of the row following the last processed one.
+    pub next_row: usize,
+}
+
+impl<T: Write> Printer<T> {
+    /// Prints one label per CSV row, filling the template placeholders from the row's columns.
+    /// The first CSV record is the header naming the columns.
+    ///
+    /// Rows are streamed, so arbitrarily large files can be printed. Every label is drawn into memory
+    /// before anything is sent, so a row with invalid content never leaves a partial label on the printer.
+    /// If a row fails, [`Error::Row`] carries its index so the batch can be resumed with [`BatchOptions::start_row`].
+    pub fn print_batch<R: Read>(
+        &mut self,
+        template: &Template,
+        csv: R,
+        options: &BatchOptions,
+    ) -> Result<BatchReport> {
+        let mut reader = csv::Reader::from_reader(csv);
+        let headers: Vec<String> = reader
+            .headers()
+            .map_err(|e| Error::Csv(e.to_string()))?
+            .iter()
+            .map(|header| {
+                options
+                    .columns
+                    .get(header)
+                    .cloned()
+                    .unwrap_or_else(|| header.to_owned())
+            })
+            .collect();
+
+        let mut report = BatchReport {
+            next_row: options.start_row,
+            ..Default::default()
+        };
+
+        for (row, record) in reader.records().enumerate().skip(options.start_row) {
+            let job = record
+                .map_err(|e| Error::Csv(e.to_string()))
+                .and_then(|record| {
+                    let vars: HashMap<&str, String> = headers
+                        .iter()
+                        .map(String::as_str)
+                        .zip(record.iter().map(str::to_owned))
+                        .collect();
+                    template.render(&vars)
+                })
+                .and_then(|label| self.prepare(&label, options.copies));
+
+            match job {
+                Ok(job) => {
+                    self.transport
+                        .write_all(job.bytes())
+                        .map_err(|e| Error::Row {
+                            row,
+                            source: Box::new(e.into()),
+                        })?;
+                    self.codepage = job.codepage;
+                    debug!("printed row {row}");
+                    report.printed += 1;
+                }
+                Err(error) if options.skip_invalid_rows => {
+                    warn!("skipping row {row}: {error}");
+                    report.skipped.push(RowError { row, error });
+                }
+                Err(error) => {
+                    return Err(Error::Row {
+                        row,
+                        source: Box::new(error),
+                    })
+                }
+            }
+            report.next_row = row + 1;
+        }
+
+        Ok(report)
+    }
+}
+
+impl<T> Printer<T> {
+    /// Draws the label into memory with the settings of this printer.
+    fn prepare(&self, label: &Label, copies: u32) -> Result<Printer<Vec<u8>>> {
+        let mut job = Printer::unconfigured(Vec::new(), self.resolution);
+        job.compress_bitmaps = self.compress_bitmaps;
+        job.codepage = self.codepage;
+        job.replacement = self.replacement;
+        job.print_label(label, copies)?;
+        Ok(job)
+    }
+}
+
+impl Printer<Vec<u8>> {
+    /// Renders a batch into memory instead of printing it, returning the TSPL job and the report.
+    pub fn dry_run_batch<R: Read>(
+        template: &Template,
+        csv: R,
+        dpi: u32,
+        options: &BatchOptions,
+    ) -> Result<(Vec<u8>, BatchReport)> {
+        let mut printer = Printer::unconfigured(Vec::new(), dpi);
+        let report = printer.print_batch(template, csv, options)?;
+        Ok((printer.take_bytes(), report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Alignment, Barcode, Codepage, Codepage8Bit, Element, Font, HumanReadable, NarrowWide,
+        Rotation, Size, Tape,
+    };
+
+    fn template() -> Template {
+        let mut label = Label::new(Tape {
+            width: Size::Dots(400),
+            height: Some(Size::Dots(200)),
+            gap: Size::Dots(16),
+            gap_offset: None,
+        });
+        label.settings.codepage = Some(Codepage::Codepage8Bit(Codepage8Bit::UnitedStates));
+        label
+            .push(Element::Text {
+                x: Size::Dots(10),
+                y: Size::Dots(10),
+                font: Font::Font8x12,
+                rotate: Rotation::NoRotation,
+                multiply_x: 1,
+                multiply_y: 1,
+                alignment: None,
+                content: "{name}".to_owned(),
+            })
+            .push(Element::Barcode {
+                x: Size::Dots(10),
+                y: Size::Dots(50),
+                code_type: Barcode::BarcodeEan13,
+                height: Size::Dots(80),
+                human_readable: HumanReadable::ReadableAlignsToCenter,
+                rotate: Rotation::NoRotation,
+                narrow_wide: NarrowWide::N2W5,
+                alignment: Some(Alignment::Left),
+                content: "{ean}".to_owned(),
+            });
+        Template::new(label)
+    }
+
+    const CSV: &str = "name,ean\n\
+        Café,4006381333931\n\
+        Bad check digit,4006381333932\n\
+        Unencodable €,400638133393\n\
+        Size 5\",400638133393\n\
+        \"Line\nbreak\",400638133393\n\
+        Tea,400638133393\n";
+
+    #[test]
+    fn skips_rows_failing_to_draw() {
+        let options = BatchOptions {
+            skip_invalid_rows: true,
+            ..Default::default()
+        };
+        let (job, report) =
+            Printer::dry_run_batch(&template(), CSV.as_bytes(), 203, &options).unwrap();
+
+        assert_eq!(report.printed, 3);
+        assert_eq!(report.next_row, 6);
+        let skipped: Vec<usize> = report.skipped.iter().map(|e| e.row).collect();
+        assert_eq!(skipped, [1, 2, 4]);
+        assert!(matches!(
+            report.skipped[0].error,
+            Error::InvalidBarcode { .. }
+        ));
+        assert!(matches!(report.skipped[1].error, Error::Unencodable { .. }));
+
+        let label = |content: &[u8]| {
+            [
+                &b"SIZE 400 dot,200 dot\r\nGAP 16 dot\r\nCODEPAGE 437\r\nCLS\r\nTEXT 10,10,\"1\",0,1,1, \""[..],
+                content,
+                b"\"\r\nBARCODE 10,50,\"EAN13\",80,2,0,2,5,1, \"400638133393\"\r\nPRINT 1\r\n",
+            ]
+            .concat()
+        };
+        assert_eq!(
+            job,
+            [label(b"Caf\x82"), label(b"Size 5\\[\"]"), label(b"Tea")].concat()
+        );
+    }
+
+    #[test]
+    fn stops_before_sending_a_failing_row() {
+        let mut printer = Printer::unconfigured(Vec::new(), 203);
+        let error = printer
+            .print_batch(&template(), CSV.as_bytes(), &BatchOptions::default())
+            .unwrap_err();
+        assert!(matches!(error, Error::Row { row: 1, .. }));
+        assert!(printer.bytes().ends_with(b"PRINT 1\r\n"));
+        assert_eq!(
+            printer
+                .bytes()
+                .windows(5)
+                .filter(|w| w == b"CLS\r\n")
+                .count(),
+            1
+        );
+
+        let options = BatchOptions {
+            start_row: 5,
+            ..Default::default()
+        };
+        let report = printer
+            .print_batch(&template(), CSV.as_bytes(), &options)
+            .unwrap();
+        assert_eq!((report.printed, report.next_row), (1, 6));
+    }
+}
@@ -0,0 +1,490 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+use tspl2::{
+    BatchOptions, Error, Label, NetworkConfig, NetworkTransport, Printer, Selftest, Size, Template,
+};
+
+const EXIT_STATUS: &str = "\
+Exit status:
+  0   success
+  64  command line usage error
+  65  malformed label, template, variables or TSPL input
+  66  input file can't be read
+  69  printer not ready or command not supported
+  73  output file can't be created
+  74  I/O error talking to the printer
+  75  printer did not respond in time
+  76  printer sent an unexpected response";
+
+/// Drive TSPL/TSPL2 label printers.
+#[derive(Parser)]
+#[command(version, after_help = EXIT_STATUS)]
+struct Cli {
+    /// Printer device path, e.g. /dev/usb/lp0, or network address host[:port].
+    #[arg(short, long, env = "TSPL2_DEVICE", global = true)]
+    device: Option<String>,
+    /// Printer resolution in dots per inch.
+    #[arg(long, default_value_t = 203, global = true)]
+    dpi: u32,
+    /// Seconds to wait for printer responses.
+    #[arg(long, default_value = "2", value_parser = parse_timeout, global = true)]
+    timeout: Duration,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Send a file to the printer unchanged. `-` reads standard input.
+    Send { file: PathBuf },
+    /// Show the printer status. Exits with 69 if the printer is not ready.
+    Status {
+        /// Query the detailed `<ESC>!S` status.
+        #[arg(long)]
+        extended: bool,
+    },
+    /// Show model, firmware, memory and stored files.
+    Info,
+    /// Print a self-test page.
+    Selftest {
+        #[arg(value_enum, default_value_t = SelftestPage::All)]
+        page: SelftestPage,
+    },
+    /// Feed labels through a sensor to detect paper and gap length.
+    Calibrate {
+        #[arg(value_enum)]
+        sensor: Sensor,
+        /// Approximate paper length, e.g. 30mm. Detected automatically if omitted.
+        #[arg(long, requires = "gap")]
+        paper: Option<Size>,
+        /// Approximate gap or black mark length, e.g. 2mm.
+        #[arg(long, requires = "paper")]
+        gap: Option<Size>,
+    },
+    /// Render a TSPL job, or a JSON label or template, to a PNG file.
+    /// Text is drawn at the cell size of the selected font, barcodes are left out.
+    Preview {
+        file: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Template variable as name=value.
+        #[arg(long = "var", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+    },
+    /// Print a JSON label template.
+    Print {
+        template: PathBuf,
+        /// Template variable as name=value.
+        #[arg(long = "var", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+        /// Print one label per row of a CSV file instead, filling variables from its columns.
+        #[arg(long, conflicts_with = "vars")]
+        csv: Option<PathBuf>,
+        #[arg(long, default_value_t = 1)]
+        copies: u32,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SelftestPage {
+    All,
+    Pattern,
+    Ethernet,
+    Wlan,
+    Rs232,
+    System,
+    Z,
+    Bt,
+}
+
+impl From<SelftestPage> for Selftest {
+    fn from(page: SelftestPage) -> Self {
+        match page {
+            SelftestPage::All => Self::All,
+            SelftestPage::Pattern => Self::Pattern,
+            SelftestPage::Ethernet => Self::Ethernet,
+            SelftestPage::Wlan => Self::Wlan,
+            SelftestPage::Rs232 => Self::Rs232,
+            SelftestPage::System => Self::System,
+            SelftestPage::Z => Self::Z,
+            SelftestPage::Bt => Self::Bt,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Sensor {
+    /// Gap sensor, GAPDETECT.
+    Gap,
+    /// Black mark sensor, BLINEDETECT.
+    Bline,
+    /// Gap or black mark, AUTODETECT.
+    Auto,
+}
+
+fn parse_var(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        .ok_or_else(|| format!("expected name=value, found {s:?}"))
+}
+
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    let seconds: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("expected seconds, found {s}"))
+}
+
+/// Error reported to the user together with the exit status.
+struct Failure {
+    status: u8,
+    message: String,
+}
+
+impl Failure {
+    fn new(status: u8, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(e: Error) -> Self {
+        Self::new(exit_status(&e), e.to_string())
+    }
+}
+
+fn exit_status(e: &Error) -> u8 {
+    match e {
+        Error::Io(_) => 74,
+        Error::Timeout => 75,
+        Error::PrinterFault(_) | Error::Unsupported(_) => 69,
+        Error::InvalidResponse(_) => 76,
+        Error::InvalidParameter { .. }
+        | Error::MissingVariables(_)
+        | Error::Template(_)
+        | Error::Csv(_)
+        | Error::Parse { .. }
+        | Error::Unencodable { .. }
+        | Error::InvalidBarcode { .. } => 65,
+        Error::Row { source, .. } => exit_status(source),
+        _ => 1,
+    }
+}
+
+/// Printer connection, either a device node or a network socket.
+enum Device {
+    File(File),
+    Network(NetworkTransport),
+}
+
+impl Read for Device {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::File(file) => file.read(buf),
+            Self::Network(net) => net.read(buf),
+        }
+    }
+}
+
+impl Write for Device {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            // Device nodes are opened non-blocking for the read timeout, writes still wait.
+            Self::File(file) => loop {
+                match file.write(buf) {
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                        std::thread::sleep(Duration::from_millis(10))
+                    }
+                    result => return result,
+                }
+            },
+            Self::Network(net) => net.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::File(file) => file.flush(),
+            Self::Network(net) => net.flush(),
+        }
+    }
+}
+
+/// Anything that looks like a path is a device node, the rest a network address.
+fn is_device(device: &str) -> bool {
+    device.starts_with(['/', '.']) || Path::new(device).exists()
+}
+
+impl Cli {
+    fn printer(&self) -> Result<Printer<Device>, Failure> {
+        let device = self.device.as_deref().ok_or_else(|| {
+            Failure::new(64, "no printer given, use --device or set TSPL2_DEVICE")
+        })?;
+
+        let transport = if is_device(device) {
+            let mut options = OpenOptions::new();
+            options.read(true).write(true);
+            // Reads return WouldBlock instead of hanging, so queries time out.
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, libc::O_NONBLOCK);
+            options
+                .open(device)
+                .map(Device::File)
+                .map_err(|e| Failure::new(74, format!("{device}: {e}")))?
+        } else {
+            NetworkTransport::connect(device, NetworkConfig::default())
+                .map(Device::Network)
+                .map_err(|e| {
+                    let e = Error::from(e);
+                    Failure::new(exit_status(&e), format!("{device}: {e}"))
+                })?
+        };
+
+        let mut printer = Printer::unconfigured(transport, self.dpi);
+        printer.set_timeout(self.timeout);
+        Ok(printer)
+    }
+}
+
+fn read_input(path: &Path) -> Result<Vec<u8>, Failure> {
+    let mut data = Vec::new();
+    let result = if path == Path::new("-") {
+        std::io::stdin().read_to_end(&mut data).map(drop)
+    } else {
+        File::open(path).and_then(|mut file| file.read_to_end(&mut data).map(drop))
+    };
+    result.map_err(|e| Failure::new(66, format!("{}: {e}", path.display())))?;
+    Ok(data)
+}
+
+/// Loads a JSON template. A plain label is accepted as a template without placeholders.
+fn load_template(path: &Path) -> Result<Template, Failure> {
+    let data = read_input(path)?;
+    serde_json::from_slice::<Template>(&data)
+        .or_else(|e| {
+            serde_json::from_slice::<Label>(&data)
+                .map(Template::new)
+                .map_err(|_| e)
+        })
+        .map_err(|e| Failure::new(65, format!("{}: {e}", path.display())))
+}
+
+fn render(template: &Template, vars: &[(String, String)]) -> Result<Label, Failure> {
+    let vars: HashMap<&str, String> = vars
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.clone()))
+        .collect();
+    Ok(template.render(&vars)?)
+}
+
+fn flags(flags: &[(&str, bool)]) -> String {
+    let set: Vec<&str> = flags
+        .iter()
+        .filter_map(|(name, set)| set.then_some(*name))
+        .collect();
+    set.join(", ")
+}
+
+fn run(cli: &Cli) -> Result<u8, Failure> {
+    match &cli.command {
+        Command::Send { file } => {
+            let data = read_input(file)?;
+            let mut printer = cli.printer()?;
+            printer.get_mut().write_all(&data).map_err(Error::from)?;
+            printer.get_mut().flush().map_err(Error::from)?;
+        }
+        Command::Status { extended: false } => {
+            let status = cli.printer()?.status()?;
+            if status.is_ready() {
+                println!("ready");
+            } else {
+                println!(
+                    "not ready: {}",
+                    flags(&[
+                        ("head open", status.head_open),
+                        ("paper jam", status.paper_jam),
+                        ("paper empty", status.paper_empty),
+                        ("ribbon empty", status.ribbon_empty),
+                        ("paused", status.paused),
+                        ("cover open", status.cover_open),
+                        ("temperature out of range", status.temperature_out_of_range),
+                    ])
+                );
+                return Ok(69);
+            }
+        }
+        Command::Status { extended: true } => {
+            let status = cli.printer()?.extended_status()?;
+            println!("state: {:?}", status.state);
+            let warnings = flags(&[
+                ("paper low", status.paper_low),
+                ("ribbon low", status.ribbon_low),
+            ]);
+            let errors = flags(&[
+                ("head overheat", status.head_overheat),
+                ("motor overheat", status.motor_overheat),
+                ("head error", status.head_error),
+                ("cutter jam", status.cutter_jam),
+                ("out of memory", status.out_of_memory),
+                ("paper empty", status.paper_empty),
+                ("paper jam", status.paper_jam),
+                ("ribbon empty", status.ribbon_empty),
+                ("ribbon jam", status.ribbon_jam),
+                ("ribbon encoder error", status.ribbon_encoder_error),
+            ]);
+            if !warnings.is_empty() {
+                println!("warnings: {warnings}");
+            }
+            if !errors.is_empty() {
+                println!("errors: {errors}");
+                return Ok(69);
+            }
+        }
+        Command::Info => {
+            let info = cli.printer()?.info()?;
+            println!("model: {}", info.model.name);
+            println!("firmware: {}", info.firmware.version);
+            println!(
+                "codepage: {}, country {}",
+                info.codepage.codepage, info.codepage.country
+            );
+            println!("free memory: {} bytes", info.free_memory.bytes);
+            println!("mileage: {} km", info.mileage.kilometers);
+            println!("real time clock: {}", if info.rtc { "yes" } else { "no" });
+            println!("files: {}", info.files.join(", "));
+        }
+        Command::Selftest { page } => {
+            cli.printer()?.selftest((*page).into())?;
+        }
+        Command::Calibrate { sensor, paper, gap } => {
+            let calib = paper.zip(*gap);
+            let mut printer = cli.printer()?;
+            match sensor {
+                Sensor::Gap => printer.gap_detect(calib)?,
+                Sensor::Bline => printer.bline_detect(calib)?,
+                Sensor::Auto => printer.auto_detect(calib)?,
+            };
+        }
+        Command::Preview { file, output, vars } => {
+            let canvas = if file.extension().is_some_and(|ext| ext == "json") {
+                tspl2::preview::render(&render(&load_template(file)?, vars)?, cli.dpi)?
+            } else {
+                tspl2::preview::render_tspl(&read_input(file)?, cli.dpi)?
+            };
+            let png = File::create(output)
+                .map_err(|e| Failure::new(73, format!("{}: {e}", output.display())))?;
+            canvas.write_png(png)?;
+        }
+        Command::Print {
+            template,
+            vars,
+            csv: None,
+            copies,
+        } => {
+            let label = render(&load_template(template)?, vars)?;
+            cli.printer()?.print_label(&label, *copies)?;
+        }
+        Command::Print {
+            template,
+            csv: Some(csv),
+            copies,
+            ..
+        } => {
+            let template = load_template(template)?;
+            let rows =
+                File::open(csv).map_err(|e| Failure::new(66, format!("{}: {e}", csv.display())))?;
+            let options = BatchOptions {
+                copies: *copies,
+                ..Default::default()
+            };
+            let report = cli.printer()?.print_batch(&template, rows, &options)?;
+            eprintln!("printed {} labels", report.printed);
+        }
+    }
+    Ok(0)
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return ExitCode::from(if e.use_stderr() { 64 } else { 0 });
+        }
+    };
+
+    match run(&cli) {
+        Ok(status) => ExitCode::from(status),
+        Err(failure) => {
+            eprintln!("tspl2: {}", failure.message);
+            ExitCode::from(failure.status)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_statuses() {
+        let io = || Error::Io(std::io::Error::other("gone"));
+        assert_eq!(exit_status(&io()), 74);
+        assert_eq!(exit_status(&Error::Timeout), 75);
+        assert_eq!(exit_status(&Error::Unsupported("GAPDETECT".into())), 69);
+        assert_eq!(exit_status(&Error::InvalidResponse("?".into())), 76);
+        assert_eq!(exit_status(&Error::Template("{name".into())), 65);
+        assert_eq!(
+            exit_status(&Error::MissingVariables(vec!["name".into()])),
+            65
+        );
+        assert_eq!(exit_status(&Error::Csv("row 2".into())), 65);
+        let row = |source| Error::Row {
+            row: 2,
+            source: Box::new(source),
+        };
+        assert_eq!(exit_status(&row(Error::Timeout)), 75);
+        assert_eq!(exit_status(&row(io())), 74);
+    }
+
+    #[test]
+    fn vars() {
+        assert_eq!(
+            parse_var("name=a=b").unwrap(),
+            ("name".to_owned(), "a=b".to_owned())
+        );
+        assert_eq!(
+            parse_var("name=").unwrap(),
+            ("name".to_owned(), String::new())
+        );
+        assert!(parse_var("name").is_err());
+    }
+
+    #[test]
+    fn timeouts() {
+        assert_eq!(parse_timeout("2").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_timeout("0.25").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_timeout("0").unwrap(), Duration::ZERO);
+        for invalid in ["", "two", "-1", "inf", "NaN"] {
+            assert!(parse_timeout(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn devices() {
+        assert!(is_device("/dev/usb/lp0"));
+        assert!(is_device("./printer"));
+        assert!(is_device(env!("CARGO_MANIFEST_DIR")));
+        assert!(!is_device("192.168.1.20:9100"));
+        assert!(!is_device("printer.local:9100"));
+        assert!(!is_device("[::1]:9100"));
+    }
+}
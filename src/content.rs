@@ -0,0 +1,113 @@
+use crate::{Error, Result};
+use std::fmt::Display;
+
+/// Text placed between the double quotes of a TSPL command.
+///
+/// Double quotes are escaped as `\["]`, which the printer reads back as `"`, so the text can't end
+/// the string early. Line breaks would end the command and are rejected, as well as other control
+/// characters: ESC starts real-time commands on most firmware. Tabs are allowed.
+/// All [`Printer`](crate::Printer) methods taking content check it this way, except that
+/// [`Content::data`] also lets the GS1 separators through to barcodes, QR codes and Data Matrix.
+///
+/// ```
+/// use tspl2::Content;
+///
+/// let name = Content::new(r#"Ruler 12""#).unwrap();
+/// assert_eq!(name.to_string(), r#"Ruler 12\["]"#);
+///
+/// // Closing the string and appending arguments or commands doesn't work.
+/// let injected = Content::new(r#"A", "B"#).unwrap();
+/// assert_eq!(injected.to_string(), r#"A\["], \["]B"#);
+/// assert!(Content::new("A\"\r\nPRINT 1000").is_err());
+/// assert!(Content::new("A\nCLS").is_err());
+/// assert!(Content::new("A\x1b!R").is_err());
+/// assert!(Content::new("A\tB").is_ok());
+/// ```
+///
+/// ```
+/// use tspl2::{Font, Printer, Rotation, Size};
+///
+/// let mut printer = Printer::unconfigured(Vec::new(), 203);
+/// let text = |printer: &mut Printer<Vec<u8>>, content| {
+///     printer
+///         .text(Size::Dots(10), Size::Dots(10), Font::Font8x12, Rotation::NoRotation, 1, 1, None, content)
+///         .map(|_| ())
+/// };
+/// text(&mut printer, "6\" \"ruler\"").unwrap();
+/// assert!(text(&mut printer, "x\"\r\nPRINT 999\r\nTEXT 0,0,\"1\",0,1,1,\"y").is_err());
+/// assert!(text(&mut printer, "x\x1b!R").is_err());
+/// assert_eq!(
+///     printer.into_inner(),
+///     b"TEXT 10,10,\"1\",0,1,1, \"6\\[\"] \\[\"]ruler\\[\"]\"\r\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Content(String);
+
+impl Content {
+    /// Checks and escapes `text`.
+    pub fn new(text: &str) -> Result<Self> {
+        Self::check(
+            text,
+            |c| c == '\t',
+            "text without line breaks or control characters",
+        )
+    }
+
+    /// Checks and escapes barcode data, which may also hold the group separator GS (`\x1d`) and
+    /// the record separator RS (`\x1e`), e.g. GS ending a variable length GS1 element string.
+    /// Line breaks and ESC are rejected like in [`Content::new`].
+    ///
+    /// ```
+    /// use tspl2::Content;
+    ///
+    /// assert!(Content::data("0109501101530003\x1d10ABC").is_ok());
+    /// assert!(Content::data("06\x1dP123\x1e").is_ok());
+    /// assert!(Content::data("A\x1d\"\r\nPRINT 1").is_err());
+    /// assert!(Content::data("A\x1b!R").is_err());
+    /// assert!(Content::new("0109501101530003\x1d10ABC").is_err());
+    /// ```
+    pub fn data(text: &str) -> Result<Self> {
+        Self::check(
+            text,
+            |c| matches!(c, '\t' | '\x1d' | '\x1e'),
+            "data without line breaks or control characters other than GS and RS",
+        )
+    }
+
+    fn check(text: &str, allowed: impl Fn(char) -> bool, description: &str) -> Result<Self> {
+        if text.contains(|c: char| c.is_ascii_control() && !allowed(c)) {
+            return Err(Error::invalid("content", description, format!("{text:?}")));
+        }
+        Ok(Self(text.replace('"', "\\[\"]")))
+    }
+
+    /// The text as sent to the printer, with quotes escaped.
+    pub fn escaped(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Writes the escaped text.
+impl Display for Content {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<&str> for Content {
+    type Error = Error;
+
+    fn try_from(text: &str) -> Result<Self> {
+        Self::new(text)
+    }
+}
+
+/// Reverts the escaping of [`Content`] in quoted TSPL text.
+pub(crate) fn unescape(text: String) -> String {
+    if text.contains("\\[\"]") {
+        text.replace("\\[\"]", "\"")
+    } else {
+        text
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{Printer, Result};
+use log::debug;
+use std::io::{self, Write};
+use strum_macros::Display;
+
+/// Immediate commands. The printer executes them as soon as they arrive,
+/// ahead of any commands still waiting in its buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RealtimeCommand {
+    /// Pause printing.
+    #[strum(serialize = "\x1b!P")]
+    Pause,
+    /// Resume a paused printer.
+    #[strum(serialize = "\x1b!O")]
+    Resume,
+    /// Cancel pending jobs and clear the buffer.
+    #[strum(serialize = "\x1b!C")]
+    Cancel,
+    /// Soft reset the printer.
+    #[strum(serialize = "\x1b!R")]
+    Reset,
+    /// Enter dump mode, printing received data instead of executing it.
+    #[strum(serialize = "\x1b!D")]
+    DumpMode,
+}
+
+/// Transports which can be duplicated to send commands from another thread.
+pub trait TryClone: Sized {
+    fn try_clone(&self) -> io::Result<Self>;
+}
+
+impl TryClone for std::fs::File {
+    fn try_clone(&self) -> io::Result<Self> {
+        std::fs::File::try_clone(self)
+    }
+}
+
+impl TryClone for std::net::TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        std::net::TcpStream::try_clone(self)
+    }
+}
+
+/// Sends real-time commands to the printer independently of the [`Printer`] it was created from.
+///
+/// The handle owns a duplicate of the transport, so it can be moved to another thread
+/// to pause or cancel a long multi-copy job while the printer is busy.
+pub struct ControlHandle {
+    writer: Box<dyn Write + Send>,
+}
+
+impl ControlHandle {
+    /// Pause printing.
+    pub fn pause(&mut self) -> Result<()> {
+        send(&mut self.writer, RealtimeCommand::Pause)
+    }
+
+    /// Resume a paused printer.
+    pub fn resume(&mut self) -> Result<()> {
+        send(&mut self.writer, RealtimeCommand::Resume)
+    }
+
+    /// Cancel pending jobs and clear the buffer.
+    pub fn cancel(&mut self) -> Result<()> {
+        send(&mut self.writer, RealtimeCommand::Cancel)
+    }
+
+    /// Soft reset the printer.
+    pub fn reset(&mut self) -> Result<()> {
+        send(&mut self.writer, RealtimeCommand::Reset)
+    }
+
+    /// Enter dump mode.
+    pub fn dump_mode(&mut self) -> Result<()> {
+        send(&mut self.writer, RealtimeCommand::DumpMode)
+    }
+}
+
+fn send(writer: &mut impl Write, cmd: RealtimeCommand) -> Result<()> {
+    debug!("{cmd:?}");
+    writer.write_all(cmd.to_string().as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+impl<T: Write> Printer<T> {
+    /// Sends a real-time command. It is flushed immediately and bypasses the printer's command buffer.
+    pub fn realtime(&mut self, cmd: RealtimeCommand) -> Result<&mut Self> {
+        send(&mut self.transport, cmd)?;
+        Ok(self)
+    }
+
+    /// Pause printing with `<ESC>!P`.
+    pub fn pause(&mut self) -> Result<&mut Self> {
+        self.realtime(RealtimeCommand::Pause)
+    }
+
+    /// Resume a paused printer with `<ESC>!O`.
+    pub fn resume(&mut self) -> Result<&mut Self> {
+        self.realtime(RealtimeCommand::Resume)
+    }
+
+    /// Cancel pending jobs and clear the buffer with `<ESC>!C`.
+    pub fn cancel(&mut self) -> Result<&mut Self> {
+        self.realtime(RealtimeCommand::Cancel)
+    }
+
+    /// Soft reset the printer with `<ESC>!R`.
+    pub fn reset(&mut self) -> Result<&mut Self> {
+        self.realtime(RealtimeCommand::Reset)
+    }
+
+    /// Enter dump mode with `<ESC>!D`.
+    pub fn dump_mode(&mut self) -> Result<&mut Self> {
+        self.realtime(RealtimeCommand::DumpMode)
+    }
+}
+
+impl<T: Write + TryClone + Send + 'static> Printer<T> {
+    /// Creates a handle for sending real-time commands from another thread.
+    pub fn control_handle(&self) -> Result<ControlHandle> {
+        Ok(ControlHandle {
+            writer: Box::new(self.transport.try_clone()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NetworkConfig, NetworkTransport};
+    use std::{io::Read, net::TcpListener, thread};
+
+    #[test]
+    fn commands_from_another_thread() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let transport = NetworkTransport::connect(&addr, NetworkConfig::default()).unwrap();
+        let mut printer = Printer::unconfigured(transport, 203);
+        printer.cls().unwrap();
+        let mut control = printer.control_handle().unwrap();
+        thread::spawn(move || {
+            control.pause()?;
+            control.resume()?;
+            control.cancel()
+        })
+        .join()
+        .unwrap()
+        .unwrap();
+        printer.reset().unwrap().dump_mode().unwrap();
+        drop(printer);
+
+        assert_eq!(
+            server.join().unwrap(),
+            b"CLS\r\n\x1b!P\x1b!O\x1b!C\x1b!R\x1b!D"
+        );
+    }
+
+    #[test]
+    fn realtime_commands() {
+        let mut printer = Printer::unconfigured(Vec::new(), 203);
+        printer
+            .pause()
+            .unwrap()
+            .resume()
+            .unwrap()
+            .cancel()
+            .unwrap()
+            .realtime(RealtimeCommand::Reset)
+            .unwrap()
+            .dump_mode()
+            .unwrap();
+        assert_eq!(printer.into_inner(), b"\x1b!P\x1b!O\x1b!C\x1b!R\x1b!D");
+    }
+}
@@ -0,0 +1,293 @@
+mod dos;
+
+use crate::{Codepage, Codepage8Bit, CodepageIso, CodepageWindows, Error, Result};
+use encoding_rs::{EncoderResult, Encoding};
+use std::borrow::Cow;
+
+/// Byte encoding behind a [`Codepage`].
+enum Charset {
+    Ascii,
+    Utf8,
+    Latin1,
+    /// Upper half of a DOS codepage, see [`dos`].
+    Dos(&'static str),
+    /// `c1` tells whether bytes 0x80 to 0x9F are printable, which is not the case
+    /// for ISO codepages borrowing a Windows table.
+    Encoding {
+        encoding: &'static Encoding,
+        c1: bool,
+    },
+}
+
+impl Codepage {
+    fn charset(self) -> Charset {
+        let windows = |encoding| Charset::Encoding { encoding, c1: true };
+        let iso = |encoding| Charset::Encoding {
+            encoding,
+            c1: false,
+        };
+        match self {
+            // The national variants replace symbols like `[` and `{`, which the command syntax needs.
+            Self::Codepage7Bit(_) => Charset::Ascii,
+            Self::Codepage8Bit(codepage) => match codepage {
+                Codepage8Bit::UnitedStates => Charset::Dos(dos::CP437),
+                Codepage8Bit::Greek => Charset::Dos(dos::CP737),
+                Codepage8Bit::Multilingual => Charset::Dos(dos::CP850),
+                Codepage8Bit::Greek1 => Charset::Ascii,
+                Codepage8Bit::Slavic => Charset::Dos(dos::CP852),
+                Codepage8Bit::Cyrillic => Charset::Dos(dos::CP855),
+                Codepage8Bit::Turkish => Charset::Dos(dos::CP857),
+                Codepage8Bit::Portuguese => Charset::Dos(dos::CP860),
+                Codepage8Bit::Icelandic => Charset::Dos(dos::CP861),
+                Codepage8Bit::Hebrew => Charset::Dos(dos::CP862),
+                Codepage8Bit::CanadianFrench => Charset::Dos(dos::CP863),
+                Codepage8Bit::Arabic => Charset::Dos(dos::CP864),
+                Codepage8Bit::Nordic => Charset::Dos(dos::CP865),
+                Codepage8Bit::Russian => Charset::Dos(dos::CP866),
+                Codepage8Bit::Greek2 => Charset::Dos(dos::CP869),
+            },
+            Self::CodepageWindows(codepage) => match codepage {
+                CodepageWindows::CentralEurope => windows(encoding_rs::WINDOWS_1250),
+                CodepageWindows::Cyrillic => windows(encoding_rs::WINDOWS_1251),
+                CodepageWindows::Latin1 => windows(encoding_rs::WINDOWS_1252),
+                CodepageWindows::Greek => windows(encoding_rs::WINDOWS_1253),
+                CodepageWindows::Turkish => windows(encoding_rs::WINDOWS_1254),
+                CodepageWindows::Hebrew => windows(encoding_rs::WINDOWS_1255),
+                CodepageWindows::Arabic => windows(encoding_rs::WINDOWS_1256),
+                CodepageWindows::Baltic => windows(encoding_rs::WINDOWS_1257),
+                CodepageWindows::Vietnam => windows(encoding_rs::WINDOWS_1258),
+                CodepageWindows::Japanese => windows(encoding_rs::SHIFT_JIS),
+                CodepageWindows::ChineseSiplified => windows(encoding_rs::GBK),
+                CodepageWindows::Korean => windows(encoding_rs::EUC_KR),
+                CodepageWindows::ChineseTraditional => windows(encoding_rs::BIG5),
+                CodepageWindows::Utf8 => Charset::Utf8,
+            },
+            Self::CodepageIso(codepage) => match codepage {
+                CodepageIso::Latin1 => Charset::Latin1,
+                CodepageIso::Latin2 => iso(encoding_rs::ISO_8859_2),
+                CodepageIso::Latin3 => iso(encoding_rs::ISO_8859_3),
+                CodepageIso::Baltic => iso(encoding_rs::ISO_8859_4),
+                CodepageIso::Cyrillic => iso(encoding_rs::ISO_8859_5),
+                CodepageIso::Arabic => iso(encoding_rs::ISO_8859_6),
+                CodepageIso::Greek => iso(encoding_rs::ISO_8859_7),
+                CodepageIso::Hebrew => iso(encoding_rs::ISO_8859_8),
+                // ISO 8859-9 is Windows-1254 without the 0x80 to 0x9F range.
+                CodepageIso::Turkish => iso(encoding_rs::WINDOWS_1254),
+                CodepageIso::Latin6 => iso(encoding_rs::ISO_8859_10),
+                CodepageIso::Latin9 => iso(encoding_rs::ISO_8859_15),
+            },
+        }
+    }
+
+    /// Converts `text` to the bytes the printer expects with this codepage active.
+    /// Characters missing from the codepage are sent as `replacement`, or fail with
+    /// [`Error::Unencodable`] if it is `None`. 7 bit codepages and CP851 are limited to ASCII.
+    pub fn encode<'a>(&self, text: &'a str, replacement: Option<u8>) -> Result<Cow<'a, [u8]>> {
+        let charset = self.charset();
+        if text.is_ascii() || matches!(charset, Charset::Utf8) {
+            return Ok(Cow::Borrowed(text.as_bytes()));
+        }
+
+        let mut bytes = Vec::with_capacity(text.len());
+        let mut encoder = match charset {
+            Charset::Encoding { encoding, .. } => Some(encoding.new_encoder()),
+            _ => None,
+        };
+        for c in text.chars() {
+            let start = bytes.len();
+            let encoded = match &charset {
+                _ if c.is_ascii() => {
+                    bytes.push(c as u8);
+                    true
+                }
+                Charset::Ascii | Charset::Utf8 => false,
+                Charset::Latin1 => match u8::try_from(c) {
+                    Ok(byte) if !(0x80..0xa0).contains(&byte) => {
+                        bytes.push(byte);
+                        true
+                    }
+                    _ => false,
+                },
+                Charset::Dos(table) => match table.chars().position(|t| t == c) {
+                    Some(n) if c != '\u{fffd}' => {
+                        bytes.push(0x80 + n as u8);
+                        true
+                    }
+                    _ => false,
+                },
+                Charset::Encoding { c1, .. } => {
+                    let encoder = encoder.as_mut().expect("encoder for the charset");
+                    let mut buf = [0; 4];
+                    let mut out = [0; 8];
+                    let (result, _, written) = encoder.encode_from_utf8_without_replacement(
+                        c.encode_utf8(&mut buf),
+                        &mut out,
+                        false,
+                    );
+                    bytes.extend_from_slice(&out[..written]);
+                    result == EncoderResult::InputEmpty
+                        && (*c1 || !out[..written].iter().any(|b| (0x80..0xa0).contains(b)))
+                }
+            };
+            if !encoded {
+                bytes.truncate(start);
+                match replacement {
+                    Some(byte) => bytes.push(byte),
+                    None => {
+                        return Err(Error::Unencodable {
+                            character: c,
+                            codepage: *self,
+                        })
+                    }
+                }
+            }
+        }
+        Ok(Cow::Owned(bytes))
+    }
+
+    /// Converts bytes written with this codepage active back to text.
+    /// Returns `None` for bytes the codepage doesn't assign.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Option<Cow<'a, str>> {
+        let charset = self.charset();
+        if bytes.is_ascii() || matches!(charset, Charset::Utf8) {
+            return std::str::from_utf8(bytes).ok().map(Cow::Borrowed);
+        }
+
+        match charset {
+            Charset::Ascii | Charset::Utf8 => None,
+            Charset::Latin1 => bytes
+                .iter()
+                .map(|&b| (!(0x80..0xa0).contains(&b)).then_some(b as char))
+                .collect::<Option<String>>()
+                .map(Cow::Owned),
+            Charset::Dos(table) => bytes
+                .iter()
+                .map(|&b| match b {
+                    0..=0x7f => Some(b as char),
+                    _ => table
+                        .chars()
+                        .nth(b as usize - 0x80)
+                        .filter(|&c| c != '\u{fffd}'),
+                })
+                .collect::<Option<String>>()
+                .map(Cow::Owned),
+            Charset::Encoding { encoding, c1 } => {
+                if !c1 && bytes.iter().any(|b| (0x80..0xa0).contains(b)) {
+                    return None;
+                }
+                encoding.decode_without_bom_handling_and_without_replacement(bytes)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Codepage7Bit;
+
+    fn round_trip(codepage: Codepage, text: &str, bytes: &[u8]) {
+        assert_eq!(
+            codepage.encode(text, None).unwrap().as_ref(),
+            bytes,
+            "{codepage}"
+        );
+        assert_eq!(codepage.decode(bytes).as_deref(), Some(text), "{codepage}");
+    }
+
+    fn unencodable(codepage: Codepage, text: &str) -> char {
+        match codepage.encode(text, None) {
+            Err(Error::Unencodable { character, .. }) => character,
+            other => panic!("{codepage}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip(
+            Codepage::Codepage8Bit(Codepage8Bit::UnitedStates),
+            "Größe ░",
+            b"Gr\x94\xe1e \xb0",
+        );
+        round_trip(
+            Codepage::Codepage8Bit(Codepage8Bit::Russian),
+            "Привет",
+            b"\x8f\xe0\xa8\xa2\xa5\xe2",
+        );
+        round_trip(
+            Codepage::CodepageWindows(CodepageWindows::CentralEurope),
+            "Łódź €",
+            b"\xa3\xf3d\x9f \x80",
+        );
+        round_trip(
+            Codepage::CodepageWindows(CodepageWindows::Utf8),
+            "Łódź €",
+            "Łódź €".as_bytes(),
+        );
+        round_trip(
+            Codepage::CodepageIso(CodepageIso::Latin1),
+            "café",
+            b"caf\xe9",
+        );
+        round_trip(
+            Codepage::CodepageIso(CodepageIso::Latin2),
+            "Łódź",
+            b"\xa3\xf3d\xbc",
+        );
+        round_trip(
+            Codepage::CodepageIso(CodepageIso::Cyrillic),
+            "Привет",
+            b"\xbf\xe0\xd8\xd2\xd5\xe2",
+        );
+        round_trip(
+            Codepage::CodepageIso(CodepageIso::Turkish),
+            "ğüş",
+            b"\xf0\xfc\xfe",
+        );
+        round_trip(Codepage::CodepageIso(CodepageIso::Latin9), "€5", b"\xa45");
+        round_trip(Codepage::Codepage7Bit(Codepage7Bit::German), "[a]", b"[a]");
+    }
+
+    #[test]
+    fn iso_codepages_reject_c1() {
+        let latin1 = Codepage::CodepageIso(CodepageIso::Latin1);
+        let latin2 = Codepage::CodepageIso(CodepageIso::Latin2);
+        let turkish = Codepage::CodepageIso(CodepageIso::Turkish);
+        assert_eq!(unencodable(latin1, "\u{85}"), '\u{85}');
+        assert_eq!(unencodable(latin2, "\u{9f}"), '\u{9f}');
+        // Windows-1254 has these at 0x80 and 0x8C, ISO 8859-9 doesn't.
+        assert_eq!(unencodable(turkish, "€"), '€');
+        assert_eq!(unencodable(turkish, "Œ"), 'Œ');
+        for codepage in [latin1, latin2, turkish] {
+            for byte in 0x80..0xa0 {
+                assert_eq!(codepage.decode(&[byte]), None, "{codepage} {byte:#x}");
+            }
+            assert!(codepage.decode(&[0xa0]).is_some(), "{codepage}");
+        }
+        let windows = Codepage::CodepageWindows(CodepageWindows::Turkish);
+        round_trip(windows, "€Œ", b"\x80\x8c");
+    }
+
+    #[test]
+    fn replacement() {
+        let cp437 = Codepage::Codepage8Bit(Codepage8Bit::UnitedStates);
+        assert_eq!(unencodable(cp437, "5 €"), '€');
+        assert_eq!(
+            cp437.encode("5 € ü", Some(b'?')).unwrap().as_ref(),
+            b"5 ? \x81"
+        );
+        let latin2 = Codepage::CodepageIso(CodepageIso::Latin2);
+        assert_eq!(unencodable(latin2, "Łódź €"), '€');
+        assert_eq!(
+            latin2.encode("€ Łódź ₽", Some(b'?')).unwrap().as_ref(),
+            b"? \xa3\xf3d\xbc ?"
+        );
+        let ascii = Codepage::Codepage7Bit(Codepage7Bit::Usa);
+        assert_eq!(unencodable(ascii, "naïve"), 'ï');
+        assert_eq!(
+            ascii.encode("naïve", Some(b'_')).unwrap().as_ref(),
+            b"na_ve"
+        );
+        assert_eq!(ascii.decode(b"na\xefve"), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! Upper halves of the DOS codepages, bytes 0x80 to 0xFF. `\u{fffd}` marks unassigned bytes.
+
+/// CP437, United States
+pub(super) const CP437: &str = "\
+    ÇüéâäàåçêëèïîìÄÅ\
+    ÉæÆôöòûùÿÖÜ¢£¥₧ƒ\
+    áíóúñÑªº¿⌐¬½¼¡«»\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧\
+    ╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩\
+    ≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// CP737, Greek
+pub(super) const CP737: &str = "\
+    ΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠ\
+    ΡΣΤΥΦΧΨΩαβγδεζηθ\
+    ικλμνξοπρσςτυφχψ\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧\
+    ╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    ωάέήϊίόύϋώΆΈΉΊΌΎ\
+    Ώ±≥≤ΪΫ÷≈°∙·√ⁿ²■\u{a0}";
+
+/// CP850, Multilingual Latin 1
+pub(super) const CP850: &str = "\
+    ÇüéâäàåçêëèïîìÄÅ\
+    ÉæÆôöòûùÿÖÜø£Ø×ƒ\
+    áíóúñÑªº¿®¬½¼¡«»\
+    ░▒▓│┤ÁÂÀ©╣║╗╝¢¥┐\
+    └┴┬├─┼ãÃ╚╔╩╦╠═╬¤\
+    ðÐÊËÈıÍÎÏ┘┌█▄¦Ì▀\
+    ÓßÔÒõÕµþÞÚÛÙýÝ¯´\
+    \u{ad}±‗¾¶§÷¸°¨·¹³²■\u{a0}";
+
+/// CP852, Latin 2
+pub(super) const CP852: &str = "\
+    ÇüéâäůćçłëŐőîŹÄĆ\
+    ÉĹĺôöĽľŚśÖÜŤťŁ×č\
+    áíóúĄąŽžĘę¬źČş«»\
+    ░▒▓│┤ÁÂĚŞ╣║╗╝Żż┐\
+    └┴┬├─┼Ăă╚╔╩╦╠═╬¤\
+    đĐĎËďŇÍÎě┘┌█▄ŢŮ▀\
+    ÓßÔŃńňŠšŔÚŕŰýÝţ´\
+    \u{ad}˝˛ˇ˘§÷¸°¨˙űŘř■\u{a0}";
+
+/// CP855, Cyrillic
+pub(super) const CP855: &str = "\
+    ђЂѓЃёЁєЄѕЅіІїЇјЈ\
+    љЉњЊћЋќЌўЎџЏюЮъЪ\
+    аАбБцЦдДеЕфФгГ«»\
+    ░▒▓│┤хХиИ╣║╗╝йЙ┐\
+    └┴┬├─┼кК╚╔╩╦╠═╬¤\
+    лЛмМнНоОп┘┌█▄Пя▀\
+    ЯрРсСтТуУжЖвВьЬ№\
+    \u{ad}ыЫзЗшШэЭщЩчЧ§■\u{a0}";
+
+/// CP857, Turkish
+pub(super) const CP857: &str = "\
+    ÇüéâäàåçêëèïîıÄÅ\
+    ÉæÆôöòûùİÖÜø£ØŞş\
+    áíóúñÑĞğ¿®¬½¼¡«»\
+    ░▒▓│┤ÁÂÀ©╣║╗╝¢¥┐\
+    └┴┬├─┼ãÃ╚╔╩╦╠═╬¤\
+    ºªÊËÈ\u{fffd}ÍÎÏ┘┌█▄¦Ì▀\
+    ÓßÔÒõÕµ\u{fffd}×ÚÛÙìÿ¯´\
+    \u{ad}±\u{fffd}¾¶§÷¸°¨·¹³²■\u{a0}";
+
+/// CP860, Portuguese
+pub(super) const CP860: &str = "\
+    ÇüéâãàÁçêÊèÍÔìÃÂ\
+    ÉÀÈôõòÚùÌÕÜ¢£Ù₧Ó\
+    áíóúñÑªº¿Ò¬½¼¡«»\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧\
+    ╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩\
+    ≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// CP861, Icelandic
+pub(super) const CP861: &str = "\
+    ÇüéâäàåçêëèÐðÞÄÅ\
+    ÉæÆôöþûÝýÖÜø£Ø₧ƒ\
+    áíóúÁÍÓÚ¿⌐¬½¼¡«»\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧\
+    ╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩\
+    ≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// CP862, Hebrew
+pub(super) const CP862: &str = "\
+    אבגדהוזחטיךכלםמן\
+    נסעףפץצקרשת¢£¥₧ƒ\
+    áíóúñÑªº¿⌐¬½¼¡«»\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧\
+    ╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩\
+    ≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// CP863, Canadian French
+pub(super) const CP863: &str = "\
+    ÇüéâÂà¶çêëèïî‗À§\
+    ÉÈÊôËÏûù¤ÔÜ¢£ÙÛƒ\
+    ¦´óú¨¸³¯Î⌐¬½¼¾«»\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧\
+    ╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩\
+    ≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// CP864, Arabic
+pub(super) const CP864: &str = "\
+    °·∙√▒─│┼┤┬├┴┐┌└┘\
+    β∞φ±½¼≈«»ﻷﻸ\u{fffd}\u{fffd}ﻻﻼ\u{fffd}\
+    \u{a0}\u{ad}ﺂ£¤ﺄ\u{fffd}\u{fffd}ﺎﺏﺕﺙ،ﺝﺡﺥ\
+    ٠١٢٣٤٥٦٧٨٩ﻑ؛ﺱﺵﺹ؟\
+    ¢ﺀﺁﺃﺅﻊﺋﺍﺑﺓﺗﺛﺟﺣﺧﺩ\
+    ﺫﺭﺯﺳﺷﺻﺿﻁﻅﻋﻏ¦¬÷×ﻉ\
+    ـﻓﻗﻛﻟﻣﻧﻫﻭﻯﻳﺽﻌﻎﻍﻡ\
+    ﹽ\u{651}ﻥﻩﻬﻰﻲﻐﻕﻵﻶﻝﻙﻱ■\u{fffd}";
+
+/// CP865, Nordic
+pub(super) const CP865: &str = "\
+    ÇüéâäàåçêëèïîìÄÅ\
+    ÉæÆôöòûùÿÖÜø£Ø₧ƒ\
+    áíóúñÑªº¿⌐¬½¼¡«¤\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧\
+    ╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩\
+    ≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// CP866, Russian
+pub(super) const CP866: &str = "\
+    АБВГДЕЖЗИЙКЛМНОП\
+    РСТУФХЦЧШЩЪЫЬЭЮЯ\
+    абвгдежзийклмноп\
+    ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧\
+    ╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    рстуфхцчшщъыьэюя\
+    ЁёЄєЇїЎў°∙·√№¤■\u{a0}";
+
+/// CP869, Greek
+pub(super) const CP869: &str = "\
+    \u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}Ά\u{fffd}·¬¦‘’Έ―Ή\
+    ΊΪΌ\u{fffd}\u{fffd}ΎΫ©Ώ²³ά£έήί\
+    ϊΐόύΑΒΓΔΕΖΗ½ΘΙ«»\
+    ░▒▓│┤ΚΛΜΝ╣║╗╝ΞΟ┐\
+    └┴┬├─┼ΠΡ╚╔╩╦╠═╬Σ\
+    ΤΥΦΧΨΩαβγ┘┌█▄δε▀\
+    ζηθικλμνξοπρσςτ΄\
+    \u{ad}±υφχ§ψ΅°¨ωϋΰώ■\u{a0}";
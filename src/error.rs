@@ -0,0 +1,114 @@
+use crate::{Barcode, BarcodeError, Codepage, Status};
+use std::{fmt::Display, io};
+
+/// Errors returned by the printer driver.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The transport failed to read or write.
+    Io(io::Error),
+    /// The transport or the printer did not respond in time.
+    Timeout,
+    /// A command parameter is out of the range accepted by the printer.
+    InvalidParameter {
+        field: &'static str,
+        allowed: String,
+        value: String,
+    },
+    /// The command is not supported by the driver or the printer.
+    Unsupported(String),
+    /// The printer reported a condition preventing it from printing.
+    PrinterFault(Status),
+    /// The printer answered with something that could not be understood.
+    InvalidResponse(String),
+    /// Template variables without a value.
+    MissingVariables(Vec<String>),
+    /// Malformed template or a variable its formatter can't handle.
+    Template(String),
+    /// Malformed CSV data.
+    Csv(String),
+    /// Batch printing failed at the given data row.
+    Row { row: usize, source: Box<Error> },
+    /// Malformed TSPL source. Line and column are 1-based, the column counts bytes.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// Text contains a character the active codepage doesn't have.
+    Unencodable { character: char, codepage: Codepage },
+    /// Barcode content the symbology can't encode.
+    InvalidBarcode {
+        symbology: Barcode,
+        error: BarcodeError,
+    },
+}
+
+impl Error {
+    pub(crate) fn invalid(field: &'static str, allowed: impl Display, value: impl Display) -> Self {
+        Self::InvalidParameter {
+            field,
+            allowed: allowed.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Timeout => write!(f, "Timed out waiting for the printer"),
+            Self::InvalidParameter {
+                field,
+                allowed,
+                value,
+            } => write!(f, "Wrong {field} value {value}, expected {allowed}"),
+            Self::Unsupported(cmd) => write!(f, "Unsupported command: {cmd}"),
+            Self::PrinterFault(status) => write!(f, "Printer is not ready: {status:?}"),
+            Self::InvalidResponse(response) => write!(f, "Malformed printer response: {response}"),
+            Self::MissingVariables(names) => {
+                write!(f, "Missing template variables: {}", names.join(", "))
+            }
+            Self::Template(msg) => write!(f, "Template error: {msg}"),
+            Self::Csv(msg) => f.write_str(msg),
+            Self::Row { row, source } => write!(f, "Row {row}: {source}"),
+            Self::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "Parse error at {line}:{column}: {message}"),
+            Self::Unencodable {
+                character,
+                codepage,
+            } => write!(
+                f,
+                "Character {character:?} is missing from codepage {codepage}"
+            ),
+            Self::InvalidBarcode { symbology, error } => {
+                write!(f, "Invalid {symbology} barcode content: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Row { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => Self::Timeout,
+            _ => Self::Io(e),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,232 @@
+use crate::{Error, Printer, Result};
+use log::debug;
+use std::io::Write;
+
+/// Printer memory holding downloaded files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Storage {
+    /// Volatile memory, cleared when the printer is switched off.
+    #[default]
+    Dram,
+    /// Flash memory, kept across power cycles.
+    Flash,
+}
+
+impl Storage {
+    /// Prefix selecting the storage in `DOWNLOAD` and `KILL`.
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Dram => "",
+            Self::Flash => "F,",
+        }
+    }
+}
+
+/// Checks `name` against the 8.3 file names accepted by the firmware:
+/// up to 8 characters, optionally followed by a dot and up to 3 characters of extension.
+/// `*` is only accepted if `wildcards` is set.
+pub(crate) fn check_name(name: &str, wildcards: bool) -> Result<()> {
+    let allowed = if wildcards {
+        "8.3 file name of letters, digits, '_', '-', '~' or '*'"
+    } else {
+        "8.3 file name of letters, digits, '_', '-' or '~'"
+    };
+    let valid_part = |part: &str, max: usize| {
+        (1..=max).contains(&part.len())
+            && part.chars().all(|c| {
+                c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '~') || (wildcards && c == '*')
+            })
+    };
+    let valid = match name.split_once('.') {
+        Some((base, extension)) => valid_part(base, 8) && valid_part(extension, 3),
+        None => valid_part(name, 8),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::invalid("file name", allowed, name))
+    }
+}
+
+impl<T: Write> Printer<T> {
+    /// Stores a file, e.g. a font, image or form, in printer memory.
+    /// Files in [`Storage::Dram`] are lost on power off. Free space can be checked with [`Printer::free_memory`].
+    pub fn download(&mut self, name: &str, data: &[u8], storage: Storage) -> Result<&mut Self> {
+        check_name(name, false)?;
+
+        let header = format!("DOWNLOAD {}\"{name}\",{},", storage.prefix(), data.len());
+        debug!("{header}<{} bytes>", data.len());
+
+        let mut cmd = header.into_bytes();
+        cmd.extend_from_slice(data);
+        cmd.extend_from_slice(b"\r\n");
+        self.transport.write_all(&cmd)?;
+        Ok(self)
+    }
+
+    /// Stores a TrueType font, to be used as [`Font::Custom`](crate::Font::Custom) with the same name.
+    pub fn download_ttf(&mut self, name: &str, data: &[u8], storage: Storage) -> Result<&mut Self> {
+        if !name.to_ascii_uppercase().ends_with(".TTF") {
+            return Err(Error::invalid(
+                "font name",
+                "file name ending in .TTF",
+                name,
+            ));
+        }
+        if !matches!(data.get(..4), Some(b"\0\x01\0\0" | b"true" | b"OTTO")) {
+            return Err(Error::invalid(
+                "font data",
+                "TrueType or OpenType font",
+                format!("{} bytes", data.len()),
+            ));
+        }
+        self.download(name, data, storage)
+    }
+
+    /// Deletes files from printer memory. `*` matches any characters, so `"*"` deletes all files
+    /// and `"*.BMP"` all bitmaps.
+    pub fn kill(&mut self, name: &str, storage: Storage) -> Result<&mut Self> {
+        check_name(name, true)?;
+
+        let cmd = format!("KILL {}\"{name}\"\r\n", storage.prefix());
+        debug!("{cmd}");
+        self.transport.write_all(cmd.as_bytes())?;
+        Ok(self)
+    }
+
+    /// Moves all files downloaded to DRAM into flash memory.
+    pub fn move_to_flash(&mut self) -> Result<&mut Self> {
+        let cmd = "MOVE\r\n";
+        debug!("{cmd}");
+        self.transport.write_all(cmd.as_bytes())?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printer() -> Printer<Vec<u8>> {
+        Printer::unconfigured(Vec::new(), 203)
+    }
+
+    #[test]
+    fn file_names() {
+        for name in [
+            "LOGO",
+            "LOGO.BMP",
+            "A",
+            "12345678.TTF",
+            "my_f-n~1.pc",
+            "a.b",
+        ] {
+            assert!(check_name(name, false).is_ok(), "{name}");
+        }
+        for name in [
+            "",
+            "123456789",
+            "LOGO.BMPX",
+            "LOGO.",
+            ".BMP",
+            "A.B.C",
+            "LO GO",
+            "LOGO\"",
+            "\"LOGO\"",
+            "LOGO\r\nCLS",
+            "LO\x1bGO",
+            "LOGÖ",
+            "*",
+        ] {
+            assert!(
+                matches!(
+                    check_name(name, false),
+                    Err(Error::InvalidParameter {
+                        field: "file name",
+                        ..
+                    })
+                ),
+                "{name:?}"
+            );
+        }
+
+        for name in ["*", "*.BMP", "LOGO*", "*.*"] {
+            assert!(check_name(name, true).is_ok(), "{name}");
+        }
+        for name in ["*\"", "LONGNAME*.BMP", "*.BMP*", "*\r\n"] {
+            assert!(check_name(name, true).is_err(), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn download() {
+        let mut printer = printer();
+        printer
+            .download("LOGO.PCX", b"\x0a\x05\r\n\"", Storage::Dram)
+            .unwrap()
+            .download("FORM", b"", Storage::Flash)
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"DOWNLOAD \"LOGO.PCX\",5,\x0a\x05\r\n\"\r\nDOWNLOAD F,\"FORM\",0,\r\n"
+        );
+
+        assert!(printer.download("LOGO\",9,", b"", Storage::Dram).is_err());
+        assert!(printer.download("*", b"", Storage::Dram).is_err());
+    }
+
+    #[test]
+    fn download_ttf() {
+        let mut printer = printer();
+        for magic in [&b"\0\x01\0\0"[..], b"true", b"OTTO"] {
+            printer
+                .download_ttf("FONT.TTF", &[magic, b"rest"].concat(), Storage::Flash)
+                .unwrap();
+        }
+        assert_eq!(
+            printer.take_bytes(),
+            b"DOWNLOAD F,\"FONT.TTF\",8,\0\x01\0\0rest\r\n\
+              DOWNLOAD F,\"FONT.TTF\",8,truerest\r\n\
+              DOWNLOAD F,\"FONT.TTF\",8,OTTOrest\r\n"
+        );
+
+        for data in [&b""[..], b"OTT", b"BM\0\0", b"wOFF\0\x01\0\0", b"\x89PNG"] {
+            assert!(matches!(
+                printer.download_ttf("FONT.TTF", data, Storage::Dram),
+                Err(Error::InvalidParameter {
+                    field: "font data",
+                    ..
+                })
+            ));
+        }
+        assert!(matches!(
+            printer.download_ttf("FONT.FNT", b"true", Storage::Dram),
+            Err(Error::InvalidParameter {
+                field: "font name",
+                ..
+            })
+        ));
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn kill_and_move() {
+        let mut printer = printer();
+        printer
+            .kill("*", Storage::Flash)
+            .unwrap()
+            .kill("*.BMP", Storage::Dram)
+            .unwrap()
+            .kill("LOGO.PCX", Storage::Flash)
+            .unwrap()
+            .move_to_flash()
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"KILL F,\"*\"\r\nKILL \"*.BMP\"\r\nKILL F,\"LOGO.PCX\"\r\nMOVE\r\n"
+        );
+
+        assert!(printer.kill("\"\r\nKILL \"*", Storage::Dram).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use crate::Printer;
+use crate::{Error, Result};
+use std::{
+    io::{Read, Write},
+    str::FromStr,
+};
+
+/// Model name returned by `~!T`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelInfo {
+    pub name: String,
+}
+
+impl FromStr for ModelInfo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "" => Err(Error::InvalidResponse("empty model name".to_owned())),
+            name => Ok(Self {
+                name: name.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Firmware version returned by `~!V`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirmwareVersion {
+    pub version: String,
+}
+
+impl FromStr for FirmwareVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "" => Err(Error::InvalidResponse("empty firmware version".to_owned())),
+            version => Ok(Self {
+                version: version.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Active code page and country code returned by `~!I`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodepageInfo {
+    /// Code page as used by the `CODEPAGE` command, e.g. `437` or `8859-1`.
+    pub codepage: String,
+    /// Country code as used by the `COUNTRY` command, e.g. `001`.
+    pub country: String,
+}
+
+impl FromStr for CodepageInfo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (codepage, country) = s
+            .trim()
+            .split_once(',')
+            .ok_or_else(|| Error::InvalidResponse(format!("code page {s:?}")))?;
+
+        Ok(Self {
+            codepage: codepage.trim().to_owned(),
+            country: country.trim().to_owned(),
+        })
+    }
+}
+
+/// Free memory returned by `~!A`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FreeMemory {
+    pub bytes: u64,
+}
+
+impl FromStr for FreeMemory {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = s
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidResponse(format!("free memory {s:?}")))?;
+        Ok(Self { bytes })
+    }
+}
+
+/// Printed length returned by `~!@`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mileage {
+    pub kilometers: f64,
+}
+
+impl FromStr for Mileage {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let kilometers = s
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidResponse(format!("mileage {s:?}")))?;
+        Ok(Self { kilometers })
+    }
+}
+
+/// Summary of all information queries, see [`Printer::info`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrinterInfo {
+    pub model: ModelInfo,
+    pub firmware: FirmwareVersion,
+    pub codepage: CodepageInfo,
+    pub free_memory: FreeMemory,
+    pub mileage: Mileage,
+    pub rtc: bool,
+    pub files: Vec<String>,
+}
+
+impl<T: Read + Write> Printer<T> {
+    /// Queries the model name with `~!T`.
+    pub fn model(&mut self) -> Result<ModelInfo> {
+        self.query_line(b"~!T")?.parse()
+    }
+
+    /// Queries the firmware version with `~!V`.
+    pub fn firmware_version(&mut self) -> Result<FirmwareVersion> {
+        self.query_line(b"~!V")?.parse()
+    }
+
+    /// Queries the active code page and country with `~!I`.
+    pub fn codepage_info(&mut self) -> Result<CodepageInfo> {
+        self.query_line(b"~!I")?.parse()
+    }
+
+    /// Queries the amount of free memory with `~!A`.
+    pub fn free_memory(&mut self) -> Result<FreeMemory> {
+        self.query_line(b"~!A")?.parse()
+    }
+
+    /// Queries the printed length with `~!@`.
+    pub fn mileage(&mut self) -> Result<Mileage> {
+        self.query_line(b"~!@")?.parse()
+    }
+
+    /// Queries whether a real time clock is installed with `~!C`.
+    pub fn has_rtc(&mut self) -> Result<bool> {
+        match self.query_line(b"~!C")?.trim() {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            x => Err(Error::InvalidResponse(format!("RTC {x:?}"))),
+        }
+    }
+
+    /// Lists the files downloaded to the printer memory with `~!F`.
+    pub fn files(&mut self) -> Result<Vec<String>> {
+        let response = self.query(b"~!F", |r| r.ends_with(b"\x1a"))?;
+        Ok(String::from_utf8_lossy(&response)
+            .trim_end_matches('\x1a')
+            .split(['\r', '\n'])
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Runs all information queries.
+    pub fn info(&mut self) -> Result<PrinterInfo> {
+        Ok(PrinterInfo {
+            model: self.model()?,
+            firmware: self.firmware_version()?,
+            codepage: self.codepage_info()?,
+            free_memory: self.free_memory()?,
+            mileage: self.mileage()?,
+            rtc: self.has_rtc()?,
+            files: self.files()?,
+        })
+    }
+
+    /// Sends a query whose response is terminated by carriage return.
+    fn query_line(&mut self, cmd: &[u8]) -> Result<String> {
+        let response = self.query(cmd, |r| r.ends_with(b"\r"))?;
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::tests::Mock;
+
+    #[test]
+    fn parses_replies() {
+        let mut printer = Mock::printer(
+            b"TTP-244 Pro\rV7.01 EZ\r8859-1,049\r4090368\r12.34\r1\rLOGO.BMP\rARIAL.TTF\r\x1a",
+            false,
+        );
+        let info = printer.info().unwrap();
+        assert_eq!(printer.get_ref().written, b"~!T~!V~!I~!A~!@~!C~!F");
+        assert_eq!(
+            info,
+            PrinterInfo {
+                model: ModelInfo {
+                    name: "TTP-244 Pro".to_owned()
+                },
+                firmware: FirmwareVersion {
+                    version: "V7.01 EZ".to_owned()
+                },
+                codepage: CodepageInfo {
+                    codepage: "8859-1".to_owned(),
+                    country: "049".to_owned()
+                },
+                free_memory: FreeMemory { bytes: 4090368 },
+                mileage: Mileage { kilometers: 12.34 },
+                rtc: true,
+                files: vec!["LOGO.BMP".to_owned(), "ARIAL.TTF".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn files() {
+        let mut printer = Mock::printer(b"\x1a", false);
+        assert!(printer.files().unwrap().is_empty());
+        let mut printer = Mock::printer(b"A.PCX\r\n B.BMP \r\n\x1aC.BMP\r", false);
+        assert_eq!(printer.files().unwrap(), ["A.PCX", "B.BMP"]);
+        // Names are listed until the terminator arrives.
+        let mut printer = Mock::printer(b"A.PCX\r", false);
+        assert!(matches!(printer.files(), Err(Error::Timeout)));
+    }
+
+    #[test]
+    fn rtc() {
+        let mut printer = Mock::printer(b"0\r", false);
+        assert!(!printer.has_rtc().unwrap());
+        let mut printer = Mock::printer(b"yes\r", false);
+        assert!(matches!(printer.has_rtc(), Err(Error::InvalidResponse(_))));
+    }
+
+    #[test]
+    fn malformed_replies() {
+        let invalid = |result: Result<()>| matches!(result, Err(Error::InvalidResponse(_)));
+        assert!(invalid(" \r".parse::<ModelInfo>().map(drop)));
+        assert!(invalid("".parse::<FirmwareVersion>().map(drop)));
+        assert!(invalid("437\r".parse::<CodepageInfo>().map(drop)));
+        assert!(invalid("4 MB\r".parse::<FreeMemory>().map(drop)));
+        assert!(invalid("-1\r".parse::<FreeMemory>().map(drop)));
+        assert!(invalid("12,5 km\r".parse::<Mileage>().map(drop)));
+
+        let mut printer = Mock::printer(b"\r", false);
+        assert!(matches!(printer.model(), Err(Error::InvalidResponse(_))));
+        let mut printer = Mock::printer(b"free\r", false);
+        assert!(matches!(
+            printer.free_memory(),
+            Err(Error::InvalidResponse(_))
+        ));
+        let mut printer = Mock::printer(b"4090368", false);
+        assert!(matches!(printer.free_memory(), Err(Error::Timeout)));
+    }
+}
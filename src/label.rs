@@ -0,0 +1,736 @@
+use crate::{
+    Alignment, Barcode, BitmapMode, Codepage, Font, HumanReadable, NarrowWide, Printer,
+    QrCodeJustification, Result, Rotation, RssType, Size, Tape,
+};
+use std::io::Write;
+
+/// Printer settings sent before the elements of a label. `None` leaves the printer setting untouched.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LabelSettings {
+    pub speed: Option<String>,
+    pub density: Option<u8>,
+    /// Reversed print direction and mirrored image.
+    pub direction: Option<(bool, bool)>,
+    pub reference: Option<(Size, Size)>,
+    pub offset: Option<Size>,
+    pub shift: Option<(Option<Size>, Size)>,
+    pub codepage: Option<Codepage>,
+}
+
+/// A single object drawn on a label. Each variant mirrors the [`Printer`] method of the same name.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Element {
+    Text {
+        x: Size,
+        y: Size,
+        font: Font,
+        rotate: Rotation,
+        multiply_x: u8,
+        multiply_y: u8,
+        alignment: Option<Alignment>,
+        content: String,
+    },
+    Block {
+        x: Size,
+        y: Size,
+        width: Size,
+        height: Size,
+        font: Font,
+        rotate: Rotation,
+        multiply_x: u8,
+        multiply_y: u8,
+        space: Option<Size>,
+        alignment: Option<Alignment>,
+        fit: Option<bool>,
+        content: String,
+    },
+    Barcode {
+        x: Size,
+        y: Size,
+        code_type: Barcode,
+        height: Size,
+        human_readable: HumanReadable,
+        rotate: Rotation,
+        narrow_wide: NarrowWide,
+        alignment: Option<Alignment>,
+        content: String,
+    },
+    QrCode {
+        x: Size,
+        y: Size,
+        ecc_level: u8,
+        cellwidth_dot: u8,
+        rotate: Rotation,
+        justification: Option<QrCodeJustification>,
+        content: String,
+    },
+    DataMatrix {
+        x: Size,
+        y: Size,
+        width: Size,
+        height: Size,
+        escape_symbol: Option<char>,
+        module_size: Option<Size>,
+        rotate: Option<Rotation>,
+        rectangular: Option<bool>,
+        row_size: Option<u8>,
+        col_size: Option<u8>,
+        content: String,
+    },
+    Pdf417 {
+        x: Size,
+        y: Size,
+        width: Size,
+        height: Size,
+        rotate: Rotation,
+        content: String,
+    },
+    MicroPdf417 {
+        x: Size,
+        y: Size,
+        rotate: Rotation,
+        module_width: Option<Size>,
+        module_height: Option<Size>,
+        col_num: Option<usize>,
+        content: String,
+    },
+    Aztec {
+        x: Size,
+        y: Size,
+        rotate: Rotation,
+        size: u8,
+        ecp: u16,
+        flg: bool,
+        menu: bool,
+        multi: u8,
+        reversed: bool,
+        content: String,
+    },
+    Codablock {
+        x: Size,
+        y: Size,
+        rotate: Rotation,
+        row_height: Option<Size>,
+        module_width: Option<Size>,
+        content: String,
+    },
+    Rss {
+        x: Size,
+        y: Size,
+        rss_type: RssType,
+        rotate: Rotation,
+        module_width: Size,
+        separator_height: usize,
+        seg_width: Option<usize>,
+        lin_height: Option<usize>,
+        content: String,
+    },
+    Tlc39 {
+        x: Size,
+        y: Size,
+        rotate: Rotation,
+        height: Option<Size>,
+        narrow: Option<Size>,
+        wide: Option<Size>,
+        cellwidth: Option<Size>,
+        cellheight: Option<Size>,
+        eci_number: String,
+        serial_number: String,
+        additional_data: String,
+    },
+    Bar {
+        x: Size,
+        y: Size,
+        width: Size,
+        height: Size,
+    },
+    Box {
+        x_start: Size,
+        y_start: Size,
+        x_end: Size,
+        y_end: Size,
+        thickness: Size,
+        radius: Option<Size>,
+    },
+    Circle {
+        x: Size,
+        y: Size,
+        diameter: Size,
+        thickness: Size,
+    },
+    Ellipse {
+        x: Size,
+        y: Size,
+        width: Size,
+        height: Size,
+        thickness: Size,
+    },
+    Diagonal {
+        x_start: Size,
+        y_start: Size,
+        x_end: Size,
+        y_end: Size,
+        thickness: Size,
+    },
+    Reverse {
+        x: Size,
+        y: Size,
+        width: Size,
+        height: Size,
+    },
+    Erase {
+        x: Size,
+        y: Size,
+        width: Size,
+        height: Size,
+    },
+    Bitmap {
+        x: Size,
+        y: Size,
+        width_bytes: u16,
+        height_dots: u16,
+        mode: BitmapMode,
+        data: Vec<u8>,
+    },
+    PutBmp {
+        x: Size,
+        y: Size,
+        name: String,
+        bpp: Option<u8>,
+        contrast: Option<u8>,
+    },
+    PutPcx {
+        x: Size,
+        y: Size,
+        name: String,
+    },
+    PutPng {
+        x: Size,
+        y: Size,
+        name: String,
+    },
+}
+
+/// Label document, built independently of any printer and rendered with [`Printer::print_label`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Label {
+    pub tape: Tape,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub settings: LabelSettings,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub elements: Vec<Element>,
+}
+
+impl Label {
+    /// Create an empty label with default settings.
+    pub fn new(tape: Tape) -> Self {
+        Self {
+            tape,
+            settings: LabelSettings::default(),
+            elements: Vec::new(),
+        }
+    }
+
+    /// Appends an element to the label.
+    pub fn push(&mut self, element: Element) -> &mut Self {
+        self.elements.push(element);
+        self
+    }
+}
+
+impl<T: Write> Printer<T> {
+    /// Sends the label geometry, settings and elements, then prints `copies` labels.
+    pub fn print_label(&mut self, label: &Label, copies: u32) -> Result<&mut Self> {
+        self.size(label.tape.width, label.tape.height)?
+            .gap(label.tape.gap, label.tape.gap_offset)?;
+
+        let settings = &label.settings;
+        if let Some(speed) = &settings.speed {
+            self.speed(speed)?;
+        }
+        if let Some(density) = settings.density {
+            self.density(density)?;
+        }
+        if let Some((reversed, mirrored)) = settings.direction {
+            self.direction(reversed, mirrored)?;
+        }
+        if let Some((x, y)) = settings.reference {
+            self.reference(x, y)?;
+        }
+        if let Some(offset) = settings.offset {
+            self.offset(offset)?;
+        }
+        if let Some((x, y)) = settings.shift {
+            self.shift(x, y)?;
+        }
+        if let Some(codepage) = settings.codepage {
+            self.codepage(codepage)?;
+        }
+
+        self.cls()?;
+        for element in &label.elements {
+            self.draw(element)?;
+        }
+        self.print(copies, None)
+    }
+
+    /// Draws a single element into the image buffer.
+    pub fn draw(&mut self, element: &Element) -> Result<&mut Self> {
+        match element {
+            Element::Text {
+                x,
+                y,
+                font,
+                rotate,
+                multiply_x,
+                multiply_y,
+                alignment,
+                content,
+            } => self.text(
+                *x,
+                *y,
+                font.clone(),
+                *rotate,
+                *multiply_x,
+                *multiply_y,
+                *alignment,
+                content,
+            ),
+            Element::Block {
+                x,
+                y,
+                width,
+                height,
+                font,
+                rotate,
+                multiply_x,
+                multiply_y,
+                space,
+                alignment,
+                fit,
+                content,
+            } => self.block(
+                *x,
+                *y,
+                *width,
+                *height,
+                font.clone(),
+                *rotate,
+                *multiply_x,
+                *multiply_y,
+                *space,
+                *alignment,
+                *fit,
+                content,
+            ),
+            Element::Barcode {
+                x,
+                y,
+                code_type,
+                height,
+                human_readable,
+                rotate,
+                narrow_wide,
+                alignment,
+                content,
+            } => self.barcode(
+                *x,
+                *y,
+                *code_type,
+                *height,
+                *human_readable,
+                *rotate,
+                *narrow_wide,
+                *alignment,
+                content,
+            ),
+            Element::QrCode {
+                x,
+                y,
+                ecc_level,
+                cellwidth_dot,
+                rotate,
+                justification,
+                content,
+            } => self.qrcode(
+                *x,
+                *y,
+                *ecc_level,
+                *cellwidth_dot,
+                *rotate,
+                *justification,
+                content,
+            ),
+            Element::DataMatrix {
+                x,
+                y,
+                width,
+                height,
+                escape_symbol,
+                module_size,
+                rotate,
+                rectangular,
+                row_size,
+                col_size,
+                content,
+            } => self.data_matrix(
+                *x,
+                *y,
+                *width,
+                *height,
+                *escape_symbol,
+                *module_size,
+                *rotate,
+                *rectangular,
+                *row_size,
+                *col_size,
+                content,
+            ),
+            Element::Pdf417 {
+                x,
+                y,
+                width,
+                height,
+                rotate,
+                content,
+            } => self.pdf417(*x, *y, *width, *height, *rotate, content),
+            Element::MicroPdf417 {
+                x,
+                y,
+                rotate,
+                module_width,
+                module_height,
+                col_num,
+                content,
+            } => self.mpdf417(
+                *x,
+                *y,
+                *rotate,
+                *module_width,
+                *module_height,
+                *col_num,
+                content,
+            ),
+            Element::Aztec {
+                x,
+                y,
+                rotate,
+                size,
+                ecp,
+                flg,
+                menu,
+                multi,
+                reversed,
+                content,
+            } => self.aztec(
+                *x, *y, *rotate, *size, *ecp, *flg, *menu, *multi, *reversed, content,
+            ),
+            Element::Codablock {
+                x,
+                y,
+                rotate,
+                row_height,
+                module_width,
+                content,
+            } => self.codablock(*x, *y, *rotate, *row_height, *module_width, content),
+            Element::Rss {
+                x,
+                y,
+                rss_type,
+                rotate,
+                module_width,
+                separator_height,
+                seg_width,
+                lin_height,
+                content,
+            } => self.rss(
+                *x,
+                *y,
+                *rss_type,
+                *rotate,
+                *module_width,
+                *separator_height,
+                *seg_width,
+                *lin_height,
+                content,
+            ),
+            Element::Tlc39 {
+                x,
+                y,
+                rotate,
+                height,
+                narrow,
+                wide,
+                cellwidth,
+                cellheight,
+                eci_number,
+                serial_number,
+                additional_data,
+            } => self.tlc39(
+                *x,
+                *y,
+                *rotate,
+                *height,
+                *narrow,
+                *wide,
+                *cellwidth,
+                *cellheight,
+                eci_number,
+                serial_number,
+                additional_data,
+            ),
+            Element::Bar {
+                x,
+                y,
+                width,
+                height,
+            } => self.bar(*x, *y, *width, *height),
+            Element::Box {
+                x_start,
+                y_start,
+                x_end,
+                y_end,
+                thickness,
+                radius,
+            } => self.rectangle(*x_start, *y_start, *x_end, *y_end, *thickness, *radius),
+            Element::Circle {
+                x,
+                y,
+                diameter,
+                thickness,
+            } => self.circle(*x, *y, *diameter, *thickness),
+            Element::Ellipse {
+                x,
+                y,
+                width,
+                height,
+                thickness,
+            } => self.ellipse(*x, *y, *width, *height, *thickness),
+            Element::Diagonal {
+                x_start,
+                y_start,
+                x_end,
+                y_end,
+                thickness,
+            } => self.diagonal(*x_start, *y_start, *x_end, *y_end, *thickness),
+            Element::Reverse {
+                x,
+                y,
+                width,
+                height,
+            } => self.reverse(*x, *y, *width, *height),
+            Element::Erase {
+                x,
+                y,
+                width,
+                height,
+            } => self.erase(*x, *y, *width, *height),
+            Element::Bitmap {
+                x,
+                y,
+                width_bytes,
+                height_dots,
+                mode,
+                data,
+            } => self.bitmap(*x, *y, *width_bytes, *height_dots, *mode, data.clone()),
+            Element::PutBmp {
+                x,
+                y,
+                name,
+                bpp,
+                contrast,
+            } => self.put_bmp(*x, *y, name, *bpp, *contrast),
+            Element::PutPcx { x, y, name } => self.put_pcx(*x, *y, name),
+            Element::PutPng { x, y, name } => self.put_png(*x, *y, name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Codepage8Bit, Error};
+
+    fn label() -> Label {
+        let mut label = Label::new(Tape {
+            width: Size::Metric(50.0),
+            height: Some(Size::Metric(30.0)),
+            gap: Size::Metric(3.0),
+            gap_offset: None,
+        });
+        label.settings = LabelSettings {
+            speed: Some("4".to_owned()),
+            density: Some(10),
+            direction: Some((true, false)),
+            reference: Some((Size::Dots(8), Size::Dots(16))),
+            offset: Some(Size::Metric(1.5)),
+            shift: Some((None, Size::Dots(4))),
+            codepage: Some(Codepage::Codepage8Bit(Codepage8Bit::UnitedStates)),
+        };
+        label
+            .push(Element::Text {
+                x: Size::Dots(10),
+                y: Size::Dots(20),
+                font: Font::Font12x20,
+                rotate: Rotation::NoRotation,
+                multiply_x: 2,
+                multiply_y: 2,
+                alignment: Some(Alignment::Center),
+                content: "Größe 12\"".to_owned(),
+            })
+            .push(Element::Bar {
+                x: Size::Dots(0),
+                y: Size::Dots(60),
+                width: Size::Dots(400),
+                height: Size::Dots(4),
+            })
+            .push(Element::Box {
+                x_start: Size::Dots(0),
+                y_start: Size::Dots(0),
+                x_end: Size::Dots(399),
+                y_end: Size::Dots(239),
+                thickness: Size::Dots(2),
+                radius: Some(Size::Dots(8)),
+            })
+            .push(Element::Barcode {
+                x: Size::Dots(20),
+                y: Size::Dots(80),
+                code_type: Barcode::BarcodeEan13,
+                height: Size::Dots(60),
+                human_readable: HumanReadable::ReadableAlignsToCenter,
+                rotate: Rotation::NoRotation,
+                narrow_wide: NarrowWide::N2W5,
+                alignment: None,
+                content: "4006381333931".to_owned(),
+            })
+            .push(Element::QrCode {
+                x: Size::Dots(300),
+                y: Size::Dots(80),
+                ecc_level: 25,
+                cellwidth_dot: 4,
+                rotate: Rotation::Rotation90,
+                justification: None,
+                content: "https://example.com".to_owned(),
+            })
+            .push(Element::Bitmap {
+                x: Size::Dots(300),
+                y: Size::Dots(200),
+                width_bytes: 1,
+                height_dots: 2,
+                mode: BitmapMode::Or,
+                data: vec![0x0f, 0xf0],
+            })
+            .push(Element::PutPcx {
+                x: Size::Dots(0),
+                y: Size::Dots(200),
+                name: "LOGO.PCX".to_owned(),
+            });
+        label
+    }
+
+    #[test]
+    fn print_label() {
+        let mut printer = Printer::unconfigured(Vec::new(), 203);
+        printer.print_label(&label(), 2).unwrap();
+        let expected: &[&[u8]] = &[
+            b"SIZE 50 mm,30 mm\r\n",
+            b"GAP 3 mm\r\n",
+            b"SPEED 4\r\n",
+            b"DENSITY 10\r\n",
+            b"DIRECTION 1,0\r\n",
+            b"REFERENCE 8,16\r\n",
+            b"OFFSET 1.5 mm\r\n",
+            b"SHIFT 4\r\n",
+            b"CODEPAGE 437\r\n",
+            b"CLS\r\n",
+            b"TEXT 10,20,\"2\",0,2,2,2, \"Gr\x94\xe1e 12\\[\"]\"\r\n",
+            b"BAR 0,60,400,4\r\n",
+            b"BOX 0,0,399,239,2,8\r\n",
+            b"BARCODE 20,80,\"EAN13\",60,2,0,2,5, \"400638133393\"\r\n",
+            b"QRCODE 300,80,H,4,A,90,\"https://example.com\"\r\n",
+            b"BITMAP 300,200,1,2,1,\x0f\xf0\r\n",
+            b"PUTPCX 0,200,\"LOGO.PCX\"\r\n",
+            b"PRINT 2\r\n",
+        ];
+        assert_eq!(printer.into_inner(), expected.concat());
+    }
+
+    #[test]
+    fn defaults_and_errors() {
+        let mut label = label();
+        label.settings = LabelSettings::default();
+        label.elements.truncate(2);
+        let mut printer = Printer::unconfigured(Vec::new(), 203);
+        printer.print_label(&label, 1).unwrap();
+        assert_eq!(
+            printer.take_bytes(),
+            b"SIZE 50 mm,30 mm\r\nGAP 3 mm\r\nCLS\r\nTEXT 10,20,\"2\",0,2,2,2, \"Gr\xc3\xb6\xc3\x9fe 12\\[\"]\"\r\nBAR 0,60,400,4\r\nPRINT 1\r\n"
+        );
+
+        // The failing element stops the label before its PRINT command.
+        label.push(Element::QrCode {
+            x: Size::Dots(0),
+            y: Size::Dots(0),
+            ecc_level: 3,
+            cellwidth_dot: 0,
+            rotate: Rotation::NoRotation,
+            justification: None,
+            content: "x".to_owned(),
+        });
+        assert!(matches!(
+            printer.print_label(&label, 1),
+            Err(Error::InvalidParameter { .. })
+        ));
+        assert!(!printer.bytes().ends_with(b"PRINT 1\r\n"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let label = label();
+        let json = serde_json::to_string(&label).unwrap();
+        for tagged in [
+            r#"{"type":"text","x":"10dots","y":"20dots","font":"Font12x20""#,
+            r#"{"type":"bar","#,
+            r#"{"type":"box","#,
+            r#"{"type":"qr_code","#,
+            r#"{"type":"put_pcx","#,
+        ] {
+            assert!(json.contains(tagged), "{tagged} missing from {json}");
+        }
+        assert!(json.contains(r#""tape":{"width":"50mm","height":"30mm","gap":"3mm""#));
+        assert_eq!(serde_json::from_str::<Label>(&json).unwrap(), label);
+
+        // Settings and elements may be left out.
+        let minimal = r#"{"tape":{"width":"2in","height":null,"gap":"0dots","gap_offset":null}}"#;
+        let minimal = serde_json::from_str::<Label>(minimal).unwrap();
+        assert_eq!(minimal.tape.width, Size::Imperial(2.0));
+        assert_eq!(minimal.settings, LabelSettings::default());
+        assert!(minimal.elements.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_malformed_elements() {
+        let bar = r#"{"type":"bar","x":"1mm","y":"1mm","width":"20mm","height":"3dots"}"#;
+        assert!(matches!(
+            serde_json::from_str::<Element>(bar).unwrap(),
+            Element::Bar { .. }
+        ));
+        for json in [
+            r#"{"x":"1mm","y":"1mm","width":"20mm","height":"3dots"}"#,
+            r#"{"type":"Bar","x":"1mm","y":"1mm","width":"20mm","height":"3dots"}"#,
+            r#"{"type":"stripe","x":"1mm","y":"1mm","width":"20mm","height":"3dots"}"#,
+            r#"{"type":"bar","x":"1mm","y":"1mm","width":"20mm"}"#,
+            r#"{"type":"bar","x":"1cm","y":"1mm","width":"20mm","height":"3dots"}"#,
+            r#"{"type":"bar","x":1,"y":"1mm","width":"20mm","height":"3dots"}"#,
+        ] {
+            assert!(serde_json::from_str::<Element>(json).is_err(), "{json}");
+        }
+    }
+}
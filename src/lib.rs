@@ -1,9 +1,49 @@
-use anyhow::{anyhow, Ok, Result};
 use log::debug;
 use std::{fmt::Display, io::Write};
-use strum_macros::Display;
-
-#[derive(Debug, Clone)]
+use strum_macros::{Display, EnumString, IntoStaticStr};
+
+mod barcode;
+#[cfg(feature = "csv")]
+mod batch;
+mod content;
+mod control;
+mod encoding;
+mod error;
+mod files;
+mod info;
+mod label;
+mod models;
+mod parser;
+#[cfg(feature = "preview")]
+pub mod preview;
+mod raster;
+#[cfg(feature = "shaping")]
+mod shaping;
+mod status;
+mod template;
+pub mod transport;
+
+pub use barcode::BarcodeError;
+#[cfg(feature = "csv")]
+pub use batch::{BatchOptions, BatchReport, RowError};
+pub use content::Content;
+pub use control::{ControlHandle, RealtimeCommand, TryClone};
+pub use error::{Error, Result};
+pub use files::Storage;
+pub use info::{CodepageInfo, FirmwareVersion, FreeMemory, Mileage, ModelInfo, PrinterInfo};
+pub use label::{Element, Label, LabelSettings};
+pub use models::{lookup_model, KnownModel, KNOWN_MODELS};
+pub use parser::{parse, Command};
+pub use raster::{Bitmap, Dithering, GrayImage};
+#[cfg(feature = "shaping")]
+pub use shaping::OutlineFont;
+pub use status::{ExtendedStatus, PrinterState, Status};
+pub use template::{Formatter, Template};
+#[cfg(feature = "serial")]
+pub use transport::{FlowControl, SerialConfig, SerialTransport};
+pub use transport::{NetworkConfig, NetworkTransport};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Size {
     Imperial(f32),
     Metric(f32),
@@ -11,11 +51,11 @@ pub enum Size {
 }
 
 impl Size {
-    fn to_dots_raw(&self, resolution: u32) -> u32 {
+    fn to_dots_raw(self, resolution: u32) -> u32 {
         match self {
-            Self::Imperial(x) => (*x * resolution as f32) as u32,
-            Self::Metric(x) => (*x / 25.4 * resolution as f32) as u32,
-            Self::Dots(x) => *x,
+            Self::Imperial(x) => (x * resolution as f32) as u32,
+            Self::Metric(x) => (x / 25.4 * resolution as f32) as u32,
+            Self::Dots(x) => x,
         }
     }
 }
@@ -30,7 +70,55 @@ impl Display for Size {
     }
 }
 
-#[derive(Debug, Display)]
+/// Parses sizes like `20mm`, `0.5in` or `120dots`. A number without unit is in inches,
+/// the same way TSPL interprets it.
+impl std::str::FromStr for Size {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+        let invalid = || Error::invalid("size", "number followed by mm, in or dots", s);
+
+        match unit.trim() {
+            "mm" => value.parse().map(Size::Metric).map_err(|_| invalid()),
+            "" | "in" | "inch" => value.parse().map(Size::Imperial).map_err(|_| invalid()),
+            "dot" | "dots" => value.parse().map(Size::Dots).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Size {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let s = match self {
+            Self::Imperial(x) => format!("{x}in"),
+            Self::Metric(x) => format!("{x}mm"),
+            Self::Dots(x) => format!("{x}dots"),
+        };
+        serializer.serialize_str(&s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Size {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Country {
     #[strum(serialize = "1")]
     Usa = 1,
@@ -78,7 +166,8 @@ pub enum Country {
     Finnish = 358,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Codepage7Bit {
     #[strum(serialize = "USA")]
     Usa,
@@ -100,7 +189,8 @@ pub enum Codepage7Bit {
     Swiss,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Codepage8Bit {
     #[strum(serialize = "437")]
     UnitedStates,
@@ -134,7 +224,8 @@ pub enum Codepage8Bit {
     Greek2,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CodepageWindows {
     #[strum(serialize = "1250")]
     CentralEurope,
@@ -166,7 +257,8 @@ pub enum CodepageWindows {
     Utf8,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CodepageIso {
     #[strum(serialize = "8859-1")]
     Latin1,
@@ -192,7 +284,8 @@ pub enum CodepageIso {
     Latin9,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Codepage {
     Codepage7Bit(Codepage7Bit),
     Codepage8Bit(Codepage8Bit),
@@ -200,7 +293,19 @@ pub enum Codepage {
     CodepageIso(CodepageIso),
 }
 
-#[derive(Debug)]
+impl Display for Codepage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Codepage7Bit(codepage) => codepage.fmt(f),
+            Self::Codepage8Bit(codepage) => codepage.fmt(f),
+            Self::CodepageWindows(codepage) => codepage.fmt(f),
+            Self::CodepageIso(codepage) => codepage.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tape {
     pub width: Size,
     pub height: Option<Size>,
@@ -208,7 +313,8 @@ pub struct Tape {
     pub gap_offset: Option<Size>,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Selftest {
     /// Print a self-test page with whole printer information.
     #[strum(serialize = "")]
@@ -236,7 +342,8 @@ pub enum Selftest {
     Bt,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Barcode {
     /// Code 128, switching code subset automatically.
     #[strum(serialize = "128")]
@@ -357,7 +464,8 @@ pub enum Barcode {
     BarcodeLogmars,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RssType {
     ///RSS14,
     #[strum(serialize = "RSS14")]
@@ -397,7 +505,8 @@ pub enum RssType {
     Ucc128Ccc,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, PartialEq, Eq, EnumString, IntoStaticStr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Font {
     /// Monotye CG Triumvirate Bold Condensed, font width and height is stretchable
     #[strum(serialize = "0")]
@@ -468,9 +577,52 @@ pub enum Font {
     /// ZPL2 font GS
     #[strum(serialize = "GS.FNT")]
     FontZplGs,
+    /// Font file downloaded to the printer, e.g. a TrueType font uploaded with [`Printer::download_ttf`]
+    #[strum(default)]
+    Custom(String),
+}
+
+impl Font {
+    /// Whether the font is a TrueType font, taking point sizes instead of multipliers.
+    pub fn is_scalable(&self) -> bool {
+        match self {
+            Self::FontMonotye | Self::FontRoman => true,
+            Self::Custom(name) => name.to_ascii_uppercase().ends_with(".TTF"),
+            _ => false,
+        }
+    }
+
+    /// Checks the `TEXT` and `BLOCK` size arguments: point sizes for TrueType fonts,
+    /// multipliers for bitmap fonts.
+    fn check_size(&self, x: u8, y: u8) -> Result<()> {
+        if let Self::Custom(name) = self {
+            files::check_name(name, false)?;
+        }
+        let (allowed, range) = match self.is_scalable() {
+            true => ("1..=255 pt", 1..=255),
+            false => ("1..=10", 1..=10),
+        };
+        if !range.contains(&x) {
+            return Err(Error::invalid("multiply_x", allowed, x));
+        }
+        if !range.contains(&y) {
+            return Err(Error::invalid("multiply_y", allowed, y));
+        }
+        Ok(())
+    }
+}
+
+impl Display for Font {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Custom(name) => f.write_str(name),
+            font => f.write_str(font.into()),
+        }
+    }
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HumanReadable {
     #[strum(serialize = "0")]
     NotReadable = 0,
@@ -483,7 +635,8 @@ pub enum HumanReadable {
 }
 
 /// Clockwise rotation
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Rotation {
     #[strum(serialize = "0")]
     NoRotation = 0,
@@ -495,7 +648,8 @@ pub enum Rotation {
     Rotation270 = 270,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Alignment {
     #[strum(serialize = "0")]
     Default = 0,
@@ -508,7 +662,8 @@ pub enum Alignment {
 }
 
 /// Specifies width in dots for narrow and wide elements respectively.
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NarrowWide {
     #[strum(serialize = "1,1")]
     N1W1,
@@ -522,7 +677,8 @@ pub enum NarrowWide {
     N3W7,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BitmapMode {
     #[strum(serialize = "0")]
     Overwrite = 0,
@@ -532,7 +688,8 @@ pub enum BitmapMode {
     Xor = 2,
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QrCodeJustification {
     #[strum(serialize = "J1")]
     UpperLeft,
@@ -554,28 +711,243 @@ pub enum QrCodeJustification {
     BottomRight,
 }
 
-pub struct Printer {
-    file: std::fs::File,
+/// Baud rate of the printer's RS-232 port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BaudRate {
+    #[strum(serialize = "12")]
+    B1200,
+    #[strum(serialize = "24")]
+    B2400,
+    #[strum(serialize = "48")]
+    B4800,
+    #[strum(serialize = "96")]
+    B9600,
+    #[strum(serialize = "19")]
+    B19200,
+    #[strum(serialize = "38")]
+    B38400,
+    #[strum(serialize = "57")]
+    B57600,
+    #[strum(serialize = "115")]
+    B115200,
+}
+
+impl BaudRate {
+    /// Baud rate in bits per second.
+    pub fn bits_per_second(&self) -> u32 {
+        match self {
+            Self::B1200 => 1200,
+            Self::B2400 => 2400,
+            Self::B4800 => 4800,
+            Self::B9600 => 9600,
+            Self::B19200 => 19200,
+            Self::B38400 => 38400,
+            Self::B57600 => 57600,
+            Self::B115200 => 115200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Parity {
+    #[strum(serialize = "N")]
+    None,
+    #[strum(serialize = "E")]
+    Even,
+    #[strum(serialize = "O")]
+    Odd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DataBits {
+    #[strum(serialize = "7")]
+    Seven,
+    #[strum(serialize = "8")]
+    Eight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopBits {
+    #[strum(serialize = "1")]
+    One,
+    #[strum(serialize = "2")]
+    Two,
+}
+
+/// Line settings of the printer's RS-232 port, as used by `SET COM1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SerialSettings {
+    pub baud_rate: BaudRate,
+    pub parity: Parity,
+    pub data_bits: DataBits,
+    pub stop_bits: StopBits,
+}
+
+impl Default for SerialSettings {
+    /// 9600 baud, no parity, 8 data bits, 1 stop bit.
+    fn default() -> Self {
+        Self {
+            baud_rate: BaudRate::B9600,
+            parity: Parity::None,
+            data_bits: DataBits::Eight,
+            stop_bits: StopBits::One,
+        }
+    }
+}
+
+/// TSPL printer driver. Commands are written to the underlying transport `T`,
+/// which may be a device node, a socket, an in-memory buffer or any other [`Write`] implementation.
+pub struct Printer<T = std::fs::File> {
+    transport: T,
     resolution: u32,
+    timeout: std::time::Duration,
+    compress_bitmaps: bool,
+    codepage: Option<Codepage>,
+    replacement: Option<u8>,
 }
 
-impl Printer {
+impl Printer<std::fs::File> {
     /// Create a new printer with predefined resolution.
     pub fn with_resolution(path: &str, tape: Tape, dpi: u32) -> Result<Self> {
         let file = std::fs::File::options().read(true).write(true).open(path)?;
-        let mut printer = Self {
-            file,
+        Printer::with_transport(file, tape, dpi)
+    }
+}
+
+impl Printer<NetworkTransport> {
+    /// Create a new network printer with predefined resolution.
+    /// `addr` is `host:port` or just `host`, in which case `config.port` (9100 by default) is used.
+    pub fn connect(addr: &str, config: NetworkConfig, tape: Tape, dpi: u32) -> Result<Self> {
+        let transport = NetworkTransport::connect(addr, config)?;
+        Printer::with_transport(transport, tape, dpi)
+    }
+}
+
+#[cfg(feature = "serial")]
+impl Printer<SerialTransport> {
+    /// Create a new printer attached to a serial port with predefined resolution.
+    pub fn open_serial(path: &str, config: SerialConfig, tape: Tape, dpi: u32) -> Result<Self> {
+        let transport = SerialTransport::open(path, config)?;
+        Printer::with_transport(transport, tape, dpi)
+    }
+
+    /// Changes the printer's RS-232 settings with `SET COM1` and switches the port to them.
+    pub fn switch_com1(&mut self, settings: SerialSettings) -> Result<&mut Self> {
+        self.set_com1(settings)?;
+        // Give the printer time to drain the command before the line settings change.
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        self.transport.reconfigure(settings)?;
+        Ok(self)
+    }
+}
+
+impl Printer<Vec<u8>> {
+    /// Create a printer which accumulates commands in memory instead of sending them to a device.
+    /// The job bytes can be taken with [`Printer::take_bytes`] and shipped or stored by other means.
+    pub fn in_memory(tape: Tape, dpi: u32) -> Result<Self> {
+        Printer::with_transport(Vec::new(), tape, dpi)
+    }
+
+    /// Returns the commands accumulated so far.
+    pub fn bytes(&self) -> &[u8] {
+        &self.transport
+    }
+
+    /// Takes the accumulated commands, leaving the buffer empty.
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.transport)
+    }
+}
+
+impl<T> Printer<T> {
+    /// Wraps the transport without sending anything to the printer.
+    /// Useful for status queries and for jobs which carry their own `SIZE` and `GAP`.
+    pub fn unconfigured(transport: T, dpi: u32) -> Self {
+        Self {
+            transport,
             resolution: dpi,
-        };
+            timeout: std::time::Duration::from_secs(2),
+            compress_bitmaps: true,
+            codepage: None,
+            replacement: None,
+        }
+    }
 
-        printer
-            .size(tape.width, tape.height)?
-            .gap(tape.gap, tape.gap_offset)?
-            .cls()?;
+    /// Returns a reference to the underlying transport.
+    pub fn get_ref(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns a mutable reference to the underlying transport.
+    /// Writing to it directly bypasses the command methods of the printer.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Consumes the printer, returning the underlying transport.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Returns the resolution used to convert sizes into dots.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    /// Sets how long to wait for the printer to answer a status or information query. Default is 2 seconds.
+    ///
+    /// The timeout is checked between reads, so it only applies to transports whose reads return
+    /// in time, like [`NetworkTransport`] and `SerialTransport` with their read timeouts.
+    /// Reading a device file such as `/dev/usb/lp0` blocks until the printer answers.
+    pub fn set_timeout(&mut self, timeout: std::time::Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Enables sending `BITMAP` data zlib-compressed (mode 3) whenever that is smaller. Enabled by default,
+    /// disable it for firmware without compressed bitmap support.
+    pub fn set_bitmap_compression(&mut self, enabled: bool) -> &mut Self {
+        self.compress_bitmaps = enabled;
+        self
+    }
+
+    /// Sets the byte sent instead of text characters missing from the active codepage, e.g. `Some(b'?')`.
+    /// With `None`, the default, such text fails with [`Error::Unencodable`].
+    pub fn set_replacement(&mut self, replacement: Option<u8>) -> &mut Self {
+        self.replacement = replacement;
+        self
+    }
+
+    /// Encodes a command carrying text for the codepage selected with [`Printer::codepage`].
+    /// Until a codepage is selected, text is sent as UTF-8.
+    fn encode<'a>(&self, cmd: &'a str) -> Result<std::borrow::Cow<'a, [u8]>> {
+        match self.codepage {
+            Some(codepage) => codepage.encode(cmd, self.replacement),
+            None => Ok(std::borrow::Cow::Borrowed(cmd.as_bytes())),
+        }
+    }
+}
 
+impl<T: Write> Printer<T> {
+    /// Create a new printer on top of an already opened transport with predefined resolution.
+    pub fn with_transport(transport: T, tape: Tape, dpi: u32) -> Result<Self> {
+        let mut printer = Self::unconfigured(transport, dpi);
+        printer.setup(tape)?;
         Ok(printer)
     }
 
+    /// Sends the tape geometry and clears the image buffer.
+    fn setup(&mut self, tape: Tape) -> Result<&mut Self> {
+        self.size(tape.width, tape.height)?
+            .gap(tape.gap, tape.gap_offset)?
+            .cls()
+    }
+
     /// This command defines the label width and height.
     /// Label length must be provided for firmware version <V8.13
     fn size(&mut self, width: Size, height: Option<Size>) -> Result<&mut Self> {
@@ -585,7 +957,7 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
 
         Ok(self)
     }
@@ -599,7 +971,7 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
 
         Ok(self)
     }
@@ -625,7 +997,7 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -649,7 +1021,7 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -673,7 +1045,7 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -683,7 +1055,7 @@ impl Printer {
         let cmd = format!("BLINE {black_line_height},{extra_feeding_len}\r\n");
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -694,7 +1066,7 @@ impl Printer {
     pub fn offset(&mut self, offset: Size) -> Result<&mut Self> {
         let cmd = format!("OFFSET {offset}\r\n");
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -703,19 +1075,19 @@ impl Printer {
     pub fn speed(&mut self, speed: &str) -> Result<&mut Self> {
         let cmd = format!("SPEED {speed}\r\n");
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
     /// This command sets the printing darkness from lightest(0) to darkest(15). Default density is 8.
     pub fn density(&mut self, density: u8) -> Result<&mut Self> {
         let cmd = match density {
-            1..=15 => format!("DENSITY {density}\r\n"),
-            _ => return Err(anyhow!("Density should be in range 0..15")),
+            0..=15 => format!("DENSITY {density}\r\n"),
+            _ => return Err(Error::invalid("density", "0..=15", density)),
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -730,7 +1102,7 @@ impl Printer {
             reversed_direction as u8, mirrored_image as u8
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
 
         Ok(self)
     }
@@ -744,7 +1116,7 @@ impl Printer {
         );
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -761,7 +1133,7 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -770,15 +1142,17 @@ impl Printer {
     pub fn country(&mut self, country: Country) -> Result<&mut Self> {
         let cmd = format!("COUNTRY {:03}\r\n", country as u16);
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
     /// This command defines the code page of international character set.
+    /// Text of later `TEXT`, `BLOCK` and `BARCODE` commands is encoded for it.
     pub fn codepage(&mut self, codepage: Codepage) -> Result<&mut Self> {
         let cmd = format!("CODEPAGE {codepage}\r\n");
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
+        self.codepage = Some(codepage);
         Ok(self)
     }
 
@@ -786,7 +1160,7 @@ impl Printer {
     pub fn cls(&mut self) -> Result<&mut Self> {
         let cmd = "CLS\r\n";
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -795,15 +1169,10 @@ impl Printer {
         let feed_dot = feed.to_dots_raw(self.resolution);
         let cmd = match feed_dot {
             0..=9999 => format!("FEED {feed_dot}\r\n"),
-            _ => {
-                return Err(anyhow!(
-                    "feed length must be in range 0..9999 in dots, got {:?}",
-                    feed_dot
-                ))
-            }
+            _ => return Err(Error::invalid("feed", "0..=9999 dots", feed_dot)),
         };
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -813,15 +1182,10 @@ impl Printer {
         let feed_dot = feed.to_dots_raw(self.resolution);
         let cmd = match feed_dot {
             0..=9999 => format!("BACKUP {feed_dot}\r\n"),
-            _ => {
-                return Err(anyhow!(
-                    "backup length must be in range 0..9999, got {:?}",
-                    feed_dot
-                ))
-            }
+            _ => return Err(Error::invalid("backup", "0..=9999 dots", feed_dot)),
         };
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -831,15 +1195,10 @@ impl Printer {
         let feed_dot = feed.to_dots_raw(self.resolution);
         let cmd = match feed_dot {
             0..=9999 => format!("BACKFEED {feed_dot}\r\n"),
-            _ => {
-                return Err(anyhow!(
-                    "backfeed length must be in range 0..9999, got {:?}",
-                    feed_dot
-                ))
-            }
+            _ => return Err(Error::invalid("backfeed", "0..=9999 dots", feed_dot)),
         };
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -847,7 +1206,7 @@ impl Printer {
     pub fn formfeed(&mut self) -> Result<&mut Self> {
         let cmd = "FORMFEED\r\n";
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -858,7 +1217,7 @@ impl Printer {
     pub fn home(&mut self) -> Result<&mut Self> {
         let cmd = "HOME\r\n";
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -869,27 +1228,17 @@ impl Printer {
                 if let Some(copies) = copies {
                     match copies {
                         1..=999999999 => format!("PRINT {sets},{copies}\r\n"),
-                        _ => {
-                            return Err(anyhow!(
-                                "Copies qty must be in range 1..999999999, got {:?}",
-                                copies
-                            ))
-                        }
+                        _ => return Err(Error::invalid("copies", "1..=999999999", copies)),
                     }
                 } else {
                     format!("PRINT {sets}\r\n")
                 }
             }
-            _ => {
-                return Err(anyhow!(
-                    "Sets qty must be in range 1..999999999, got {:?}",
-                    sets
-                ))
-            }
+            _ => return Err(Error::invalid("sets", "1..=999999999", sets)),
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -898,11 +1247,12 @@ impl Printer {
     pub fn sound(&mut self, level: u8, interval: u16) -> Result<&mut Self> {
         let cmd = match (level, interval) {
             (0..=9, 1..=4095) => format!("SOUND {level},{interval}\r\n"),
-            _ => return Err(anyhow!("wrong sound parameters")),
+            (0..=9, _) => return Err(Error::invalid("interval", "1..=4095", interval)),
+            _ => return Err(Error::invalid("level", "0..=9", level)),
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -910,7 +1260,7 @@ impl Printer {
     pub fn cut(&mut self) -> Result<&mut Self> {
         let cmd = "CUT\r\n";
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -935,7 +1285,7 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
 
         Ok(self)
     }
@@ -944,7 +1294,20 @@ impl Printer {
     pub fn selftest(&mut self, test_kind: Selftest) -> Result<&mut Self> {
         let cmd = format!("SELFTEST {test_kind}\r\n");
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
+        Ok(self)
+    }
+
+    /// This command sets the RS-232 port parameters of the printer.
+    /// The new settings take effect immediately, so the host side has to be reconfigured afterwards.
+    pub fn set_com1(&mut self, settings: SerialSettings) -> Result<&mut Self> {
+        let cmd = format!(
+            "SET COM1 {},{},{},{}\r\n",
+            settings.baud_rate, settings.parity, settings.data_bits, settings.stop_bits
+        );
+        debug!("{cmd}");
+        self.transport.write_all(cmd.as_bytes())?;
+        self.transport.flush()?;
         Ok(self)
     }
 
@@ -952,7 +1315,7 @@ impl Printer {
     pub fn eoj(&mut self) -> Result<&mut Self> {
         let cmd = "EOJ\r\n";
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -960,26 +1323,26 @@ impl Printer {
     pub fn delay(&mut self, delay: std::time::Duration) -> Result<&mut Self> {
         let cmd = format!("DELAY {}\r\n", delay.as_millis());
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
     /// This command can show the image, which is in printer’s image buffer, on LCD panel.
-    pub fn display(&self) {
-        unimplemented!()
+    pub fn display(&mut self) -> Result<&mut Self> {
+        Err(Error::Unsupported("DISPLAY".to_owned()))
     }
 
     /// This command can restore printer settings to defaults.
     pub fn initial_printer(&mut self) -> Result<&mut Self> {
         let cmd = "INITIALPRINTER\r\n";
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
     /// This command can design user's own menu with a database resident on the printer.
-    pub fn menu(&self) {
-        unimplemented!()
+    pub fn menu(&mut self) -> Result<&mut Self> {
+        Err(Error::Unsupported("MENU".to_owned()))
     }
 
     /// This command draws a bar on the label format.
@@ -998,11 +1361,13 @@ impl Printer {
             height.to_dots_raw(self.resolution)
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
     /// This command prints 1D barcodes.
+    /// The content may hold the GS1 separators GS and RS, see [`Content::data`].
+    #[allow(clippy::too_many_arguments)]
     pub fn barcode(
         &mut self,
         x: Size,
@@ -1015,6 +1380,7 @@ impl Printer {
         alignment: Option<Alignment>,
         content: &str,
     ) -> Result<&mut Self> {
+        let content = Content::data(&code_type.validate(content)?)?;
         let cmd = if let Some(alignment) = alignment {
             format!(
                 "BARCODE {},{},\"{}\",{},{},{},{},{}, \"{}\"\r\n",
@@ -1043,11 +1409,12 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(&self.encode(&cmd)?)?;
         Ok(self)
     }
 
     /// This command draws TLC39, TCIF Linked Bar Code 3 of 9, barcode.
+    #[allow(clippy::too_many_arguments)]
     pub fn tlc39(
         &mut self,
         x: Size,
@@ -1062,6 +1429,9 @@ impl Printer {
         serial_number: &str,
         additional_data: &str,
     ) -> Result<&mut Self> {
+        let eci_number = Content::new(eci_number)?;
+        let serial_number = Content::new(serial_number)?;
+        let additional_data = Content::new(additional_data)?;
         let x = x.to_dots_raw(self.resolution);
         let y = y.to_dots_raw(self.resolution);
         let height = height
@@ -1091,11 +1461,12 @@ impl Printer {
             additional_data
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
     /// This command draws bitmap images (as opposed to BMP graphic files).
+    /// In [`BitmapMode::Overwrite`] the data is sent compressed if that is smaller, see [`Printer::set_bitmap_compression`].
     pub fn bitmap(
         &mut self,
         x: Size,
@@ -1106,21 +1477,109 @@ impl Printer {
         bitmap_data: Vec<u8>,
     ) -> Result<&mut Self> {
         let crlf = vec![b'\r', b'\n'];
+        let x = x.to_dots_raw(self.resolution);
+        let y = y.to_dots_raw(self.resolution);
+
+        let compressed = match mode {
+            BitmapMode::Overwrite if self.compress_bitmaps => {
+                let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&bitmap_data, 6);
+                let header = compressed.len().to_string().len() + 1;
+                (compressed.len() + header < bitmap_data.len()).then_some(compressed)
+            }
+            _ => None,
+        };
+
+        let mut cmd = match compressed {
+            Some(compressed) => {
+                debug!(
+                    "BITMAP data compressed from {} to {} bytes, {} saved",
+                    bitmap_data.len(),
+                    compressed.len(),
+                    bitmap_data.len() - compressed.len()
+                );
+                let mut cmd = format!(
+                    "BITMAP {x},{y},{width_bytes},{height_dots},3,{},",
+                    compressed.len()
+                )
+                .as_bytes()
+                .to_vec();
+                cmd.extend(compressed);
+                cmd
+            }
+            None => {
+                let mut cmd = format!("BITMAP {x},{y},{width_bytes},{height_dots},{mode},")
+                    .as_bytes()
+                    .to_vec();
+                cmd.extend(bitmap_data);
+                cmd
+            }
+        };
+        cmd.extend(crlf);
+
+        self.transport.write_all(&cmd)?;
+
+        Ok(self)
+    }
+
+    /// This command draws a BMP file stored in printer memory.
+    /// `bpp` selects 1 bit or 8 bit grayscale rendering, `contrast` (0..=100) applies to 8 bit rendering.
+    pub fn put_bmp(
+        &mut self,
+        x: Size,
+        y: Size,
+        name: &str,
+        bpp: Option<u8>,
+        contrast: Option<u8>,
+    ) -> Result<&mut Self> {
+        files::check_name(name, false)?;
         let mut cmd = format!(
-            "BITMAP {},{},{},{},{},",
+            "PUTBMP {},{},\"{name}\"",
             x.to_dots_raw(self.resolution),
-            y.to_dots_raw(self.resolution),
-            width_bytes,
-            height_dots,
-            mode
-        )
-        .as_bytes()
-        .to_vec();
-        cmd.extend(bitmap_data);
-        cmd.extend(crlf);
+            y.to_dots_raw(self.resolution)
+        );
 
-        self.file.write_all(&cmd)?;
+        match (bpp, contrast) {
+            (None, None) => {}
+            (Some(bpp @ (1 | 8)), None) => cmd.push_str(&format!(",{bpp}")),
+            (Some(8), Some(contrast @ 0..=100)) => cmd.push_str(&format!(",8,{contrast}")),
+            (Some(8) | None, Some(contrast)) => {
+                return Err(Error::invalid("contrast", "0..=100 with 8 bpp", contrast))
+            }
+            (Some(1), Some(contrast)) => {
+                return Err(Error::invalid("contrast", "None with 1 bpp", contrast))
+            }
+            (Some(bpp), _) => return Err(Error::invalid("bpp", "1 or 8", bpp)),
+        }
+        cmd.push_str("\r\n");
 
+        debug!("{cmd}");
+        self.transport.write_all(cmd.as_bytes())?;
+        Ok(self)
+    }
+
+    /// This command draws a PCX file stored in printer memory.
+    pub fn put_pcx(&mut self, x: Size, y: Size, name: &str) -> Result<&mut Self> {
+        files::check_name(name, false)?;
+        let cmd = format!(
+            "PUTPCX {},{},\"{name}\"\r\n",
+            x.to_dots_raw(self.resolution),
+            y.to_dots_raw(self.resolution)
+        );
+        debug!("{cmd}");
+        self.transport.write_all(cmd.as_bytes())?;
+        Ok(self)
+    }
+
+    /// This command draws a PNG file stored in printer memory.
+    pub fn put_png(&mut self, x: Size, y: Size, name: &str) -> Result<&mut Self> {
+        files::check_name(name, false)?;
+        let cmd = format!(
+            "PUTPNG {},{},\"{name}\"\r\n",
+            x.to_dots_raw(self.resolution),
+            y.to_dots_raw(self.resolution)
+        );
+        debug!("{cmd}");
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -1144,7 +1603,7 @@ impl Printer {
             radius.unwrap_or(Size::Dots(0)).to_dots_raw(self.resolution)
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
 
         Ok(self)
     }
@@ -1165,7 +1624,7 @@ impl Printer {
             thickness.to_dots_raw(self.resolution)
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -1187,7 +1646,7 @@ impl Printer {
             thickness.to_dots_raw(self.resolution)
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -1201,6 +1660,7 @@ impl Printer {
         module_width: Option<Size>,
         content: &str,
     ) -> Result<&mut Self> {
+        let content = Content::new(content)?;
         let row_height = row_height
             .unwrap_or(Size::Dots(8))
             .to_dots_raw(self.resolution);
@@ -1218,12 +1678,14 @@ impl Printer {
             content
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
 
         Ok(self)
     }
 
     /// This command defines a DataMatrix 2D bar code. Currently, only ECC200 error correction is supported.
+    /// The content may hold the GS1 separators GS and RS, see [`Content::data`].
+    #[allow(clippy::too_many_arguments)]
     pub fn data_matrix(
         &mut self,
         x: Size,
@@ -1238,6 +1700,7 @@ impl Printer {
         col_size: Option<u8>,
         content: &str,
     ) -> Result<&mut Self> {
+        let content = Content::data(content)?;
         let mut cmd = format!(
             "DMATRIX {},{},{},{},",
             x.to_dots_raw(self.resolution),
@@ -1247,11 +1710,10 @@ impl Printer {
         );
 
         if let Some(c) = escape_symbol {
-            let c = c as u8;
-            if (0..=127).contains(&c) {
-                cmd.push_str(&format!("c{},", c));
+            if c.is_ascii() {
+                cmd.push_str(&format!("c{},", c as u8));
             } else {
-                return Err(anyhow!("Wrong ASCII symbol"));
+                return Err(Error::invalid("escape_symbol", "ASCII character", c));
             }
         }
 
@@ -1272,7 +1734,7 @@ impl Printer {
             if (10..=144).contains(&row) {
                 cmd.push_str(&format!("{row},"));
             } else {
-                return Err(anyhow!("Row size doesnt match limit [10;144]"));
+                return Err(Error::invalid("row_size", "10..=144", row));
             }
         }
 
@@ -1280,14 +1742,14 @@ impl Printer {
             if (10..=144).contains(&col) {
                 cmd.push_str(&format!("{col},"));
             } else {
-                return Err(anyhow!("Column size doesnt match limit [10;144]"));
+                return Err(Error::invalid("col_size", "10..=144", col));
             }
         }
 
         cmd.push_str(&format!(" \"{}\"\r\n", content));
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -1301,7 +1763,7 @@ impl Printer {
             height.to_dots_raw(self.resolution)
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -1315,6 +1777,7 @@ impl Printer {
         rotate: Rotation,
         content: &str,
     ) -> Result<&mut Self> {
+        let content = Content::new(content)?;
         let cmd = format!(
             "PDF417 {},{},{},{},{},\"{}\"\r\n",
             x_start.to_dots_raw(self.resolution),
@@ -1325,11 +1788,12 @@ impl Printer {
             content
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
     /// This command defines a AZTEC 2D bar code.
+    #[allow(clippy::too_many_arguments)]
     pub fn aztec(
         &mut self,
         x_start: Size,
@@ -1344,13 +1808,13 @@ impl Printer {
         content: &str,
     ) -> Result<&mut Self> {
         if !(1..=20).contains(&size) {
-            return Err(anyhow!("Wrong size settings. min: 1, max: 20"));
+            return Err(Error::invalid("size", "1..=20", size));
         }
         if ecp > 300 {
-            return Err(anyhow!("Wrong error control parameter. Max: 300"));
+            return Err(Error::invalid("ecp", "0..=300", ecp));
         }
         if !(1..=26).contains(&multi) {
-            return Err(anyhow!("Wrong number of symbols. min: 1, max: 26"));
+            return Err(Error::invalid("multi", "1..=26", multi));
         }
 
         let cmd = format!(
@@ -1364,16 +1828,17 @@ impl Printer {
             menu as u8,
             multi,
             reversed as u8,
-            content.as_bytes().len(),
+            content.len(),
             content
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
 
         Ok(self)
     }
 
     /// This command defines a Micro PDF 417 bar code.
+    #[allow(clippy::too_many_arguments)]
     pub fn mpdf417(
         &mut self,
         x_start: Size,
@@ -1384,6 +1849,7 @@ impl Printer {
         col_num: Option<usize>,
         content: &str,
     ) -> Result<&mut Self> {
+        let content = Content::new(content)?;
         let col_num = match col_num {
             Some(x) => match x {
                 1..=4 => x,
@@ -1410,12 +1876,14 @@ impl Printer {
             content,
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
 
         Ok(self)
     }
 
     /// This command prints QR code.
+    /// The content may hold the GS1 separators GS and RS, see [`Content::data`].
+    #[allow(clippy::too_many_arguments)]
     pub fn qrcode(
         &mut self,
         x_upper_left: Size,
@@ -1426,6 +1894,7 @@ impl Printer {
         justification: Option<QrCodeJustification>,
         content: &str,
     ) -> Result<&mut Self> {
+        let content = Content::data(content)?;
         let ecc_level = match ecc_level {
             0..=6 => 'L',
             7..=14 => 'M',
@@ -1433,7 +1902,7 @@ impl Printer {
             _ => 'H',
         };
         if !(1..=10).contains(&cellwidth_dot) {
-            return Err(anyhow!("Wrong cellwidth value. min: 1, max: 10"));
+            return Err(Error::invalid("cellwidth", "1..=10", cellwidth_dot));
         }
 
         let cmd = match justification {
@@ -1459,11 +1928,12 @@ impl Printer {
         };
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
     /// This command is used to draw a RSS bar code on the label format
+    #[allow(clippy::too_many_arguments)]
     pub fn rss(
         &mut self,
         x_upper_left: Size,
@@ -1476,20 +1946,25 @@ impl Printer {
         lin_height: Option<usize>,
         content: &str,
     ) -> Result<&mut Self> {
+        let content = Content::new(content)?;
         let pix_mult = module_width.to_dots_raw(self.resolution);
         if !(1..=10).contains(&pix_mult) {
-            return Err(anyhow!("Wrong module resolution"));
+            return Err(Error::invalid("module_width", "1..=10 dots", pix_mult));
         }
 
         if separator_height != 1 && separator_height != 2 {
-            return Err(anyhow!("Wrong separator height"));
+            return Err(Error::invalid(
+                "separator_height",
+                "1 or 2",
+                separator_height,
+            ));
         }
 
         let cmd = match rss_type {
             RssType::RssExp => match seg_width {
                 Some(seg_width) => {
                     if !(2..=22).contains(&seg_width) {
-                        return Err(anyhow!("Wrong segment width. 2 to 22 accepted"));
+                        return Err(Error::invalid("seg_width", "2..=22", seg_width));
                     }
                     format!(
                         "RSS {},{}, \"{}\",{},{},{},{}, \"{}\"\r\n",
@@ -1503,12 +1978,12 @@ impl Printer {
                         content
                     )
                 }
-                None => return Err(anyhow!("Missed segment width")),
+                None => return Err(Error::invalid("seg_width", "2..=22", "None")),
             },
             RssType::Ucc128Cca | RssType::Ucc128Ccc => match lin_height {
                 Some(lin_height) => {
                     if !(1..=500).contains(&lin_height) {
-                        return Err(anyhow!("Wrong line height. 1 to 500 accepted"));
+                        return Err(Error::invalid("lin_height", "1..=500", lin_height));
                     }
                     format!(
                         "RSS {},{}, \"{}\",{},{},{},{}, \"{}\"\r\n",
@@ -1522,7 +1997,7 @@ impl Printer {
                         content
                     )
                 }
-                None => return Err(anyhow!("UCC/EAN-128 height missed")),
+                None => return Err(Error::invalid("lin_height", "1..=500", "None")),
             },
             _ => {
                 format!(
@@ -1538,7 +2013,7 @@ impl Printer {
             }
         };
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -1558,7 +2033,7 @@ impl Printer {
             height.to_dots_raw(self.resolution)
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
@@ -1580,10 +2055,13 @@ impl Printer {
             thickness.to_dots_raw(self.resolution)
         );
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(cmd.as_bytes())?;
         Ok(self)
     }
 
+    /// This command prints text. For TrueType fonts (see [`Font::is_scalable`]) `multiply_x` and
+    /// `multiply_y` are the character width and height in points, otherwise integer multipliers.
+    #[allow(clippy::too_many_arguments)]
     pub fn text(
         &mut self,
         x: Size,
@@ -1595,9 +2073,8 @@ impl Printer {
         alignment: Option<Alignment>,
         content: &str,
     ) -> Result<&mut Self> {
-        if !(1..=10).contains(&multiply_x) || !(1..=10).contains(&multiply_y) {
-            return Err(anyhow!("Wrong multiplication. Should be in range 1-10"));
-        }
+        let content = Content::new(content)?;
+        font.check_size(multiply_x, multiply_y)?;
         let cmd = match alignment {
             Some(alignment) => format!(
                 "TEXT {},{},\"{}\",{},{},{},{}, \"{}\"\r\n",
@@ -1622,10 +2099,30 @@ impl Printer {
             ),
         };
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(&self.encode(&cmd)?)?;
         Ok(self)
     }
 
+    /// Text printed with a TrueType font at `points` (1/72 inch) character height and width.
+    #[allow(clippy::too_many_arguments)]
+    pub fn text_points(
+        &mut self,
+        x: Size,
+        y: Size,
+        font: Font,
+        rotate: Rotation,
+        points: u8,
+        alignment: Option<Alignment>,
+        content: &str,
+    ) -> Result<&mut Self> {
+        if !font.is_scalable() {
+            return Err(Error::invalid("font", "TrueType font", font));
+        }
+        self.text(x, y, font, rotate, points, points, alignment, content)
+    }
+
+    /// This command prints text wrapped in a block. The sizes follow the same rules as in [`Printer::text`].
+    #[allow(clippy::too_many_arguments)]
     pub fn block(
         &mut self,
         x: Size,
@@ -1641,16 +2138,19 @@ impl Printer {
         fit: Option<bool>,
         content: &str,
     ) -> Result<&mut Self> {
-        if !(1..=10).contains(&multiply_x) || !(1..=10).contains(&multiply_y) {
-            return Err(anyhow!("Wrong multiplication. Should be in range 1-10"));
-        }
-
-        if content.len() > 4096 {
-            return Err(anyhow!("Overflow. Max content length 4096"));
+        let content = Content::new(content)?;
+        font.check_size(multiply_x, multiply_y)?;
+
+        if content.escaped().len() > 4096 {
+            return Err(Error::invalid(
+                "content length",
+                "0..=4096 bytes",
+                content.escaped().len(),
+            ));
         }
 
         let mut cmd = format!(
-            "TEXT {},{},{},{},\"{}\",{},{},{},",
+            "BLOCK {},{},{},{},\"{}\",{},{},{},",
             x.to_dots_raw(self.resolution),
             y.to_dots_raw(self.resolution),
             width.to_dots_raw(self.resolution),
@@ -1675,7 +2175,810 @@ impl Printer {
         cmd.push_str(&format!("\"{}\"\r\n", content));
 
         debug!("{cmd}");
-        self.file.write_all(cmd.as_bytes())?;
+        self.transport.write_all(&self.encode(&cmd)?)?;
         Ok(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printer() -> Printer<Vec<u8>> {
+        Printer::unconfigured(Vec::new(), 203)
+    }
+
+    /// Field of the rejected parameter.
+    fn invalid<T>(result: Result<T>) -> &'static str {
+        match result {
+            Err(Error::InvalidParameter { field, .. }) => field,
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("invalid parameter accepted"),
+        }
+    }
+
+    #[test]
+    fn setup() {
+        let tape = Tape {
+            width: Size::Metric(40.0),
+            height: Some(Size::Metric(30.0)),
+            gap: Size::Metric(2.0),
+            gap_offset: Some(Size::Metric(0.5)),
+        };
+        let mut printer = Printer::in_memory(tape, 203).unwrap();
+        assert_eq!(
+            printer.take_bytes(),
+            b"SIZE 40 mm,30 mm\r\nGAP 2 mm,0.5 mm\r\nCLS\r\n"
+        );
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn job_commands() {
+        let mut printer = printer();
+        printer
+            .density(8)
+            .unwrap()
+            .direction(true, false)
+            .unwrap()
+            .reference(Size::Imperial(0.5), Size::Dots(10))
+            .unwrap()
+            .codepage(Codepage::CodepageWindows(CodepageWindows::Latin1))
+            .unwrap()
+            .feed(Size::Metric(25.4))
+            .unwrap()
+            .print(1, None)
+            .unwrap()
+            .print(2, Some(3))
+            .unwrap()
+            .sound(5, 200)
+            .unwrap()
+            .cut()
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"DENSITY 8\r\nDIRECTION 1,0\r\nREFERENCE 101,10\r\nCODEPAGE 1252\r\nFEED 203\r\n\
+              PRINT 1\r\nPRINT 2,3\r\nSOUND 5,200\r\nCUT\r\n"
+        );
+    }
+
+    #[test]
+    fn job_command_ranges() {
+        let mut printer = printer();
+        assert_eq!(invalid(printer.density(16)), "density");
+        assert_eq!(invalid(printer.feed(Size::Dots(10000))), "feed");
+        assert_eq!(invalid(printer.backfeed(Size::Dots(10000))), "backfeed");
+        assert_eq!(invalid(printer.print(0, None)), "sets");
+        assert_eq!(invalid(printer.print(1_000_000_000, None)), "sets");
+        assert_eq!(invalid(printer.print(1, Some(0))), "copies");
+        assert_eq!(invalid(printer.print(1, Some(1_000_000_000))), "copies");
+        assert_eq!(invalid(printer.sound(10, 1)), "level");
+        assert_eq!(invalid(printer.sound(9, 0)), "interval");
+        assert_eq!(invalid(printer.sound(0, 4096)), "interval");
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn shapes() {
+        let mut printer = printer();
+        printer
+            .bar(Size::Dots(1), Size::Dots(2), Size::Dots(3), Size::Dots(4))
+            .unwrap()
+            .rectangle(
+                Size::Dots(0),
+                Size::Dots(0),
+                Size::Dots(100),
+                Size::Dots(50),
+                Size::Dots(2),
+                None,
+            )
+            .unwrap()
+            .circle(Size::Dots(5), Size::Dots(6), Size::Dots(40), Size::Dots(3))
+            .unwrap()
+            .erase(Size::Dots(1), Size::Dots(1), Size::Dots(8), Size::Dots(8))
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"BAR 1,2,3,4\r\nBOX 0,0,100,50,2,0\r\nCIRCLE 5,6,40,3\r\nERASE 1,1,8,8\r\n"
+        );
+    }
+
+    #[test]
+    fn text() {
+        let mut printer = printer();
+        printer
+            .text(
+                Size::Dots(10),
+                Size::Dots(20),
+                Font::Font24x32,
+                Rotation::Rotation90,
+                1,
+                2,
+                Some(Alignment::Right),
+                "Hi \"you\"",
+            )
+            .unwrap()
+            .text(
+                Size::Dots(0),
+                Size::Dots(0),
+                Font::Custom("ARIAL.TTF".to_owned()),
+                Rotation::NoRotation,
+                12,
+                12,
+                None,
+                "Hi",
+            )
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"TEXT 10,20,\"4\",90,1,2,3, \"Hi \\[\"]you\\[\"]\"\r\nTEXT 0,0,\"ARIAL.TTF\",0,12,12, \"Hi\"\r\n"
+        );
+
+        let mut printer = self::printer();
+        let font = Font::Font8x12;
+        let rotation = Rotation::NoRotation;
+        let at = Size::Dots(0);
+        assert_eq!(
+            invalid(printer.text(at, at, font.clone(), rotation, 11, 1, None, "x")),
+            "multiply_x"
+        );
+        assert_eq!(
+            invalid(printer.text_points(at, at, font, rotation, 12, None, "x")),
+            "font"
+        );
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn custom_fonts() {
+        for name in ["ARIAL.TTF", "arial.ttf", "MYFONT.FNT", "A"] {
+            let font = name.parse::<Font>().unwrap();
+            assert_eq!(font, Font::Custom(name.to_owned()));
+            assert_eq!(font.to_string(), name);
+        }
+        assert_eq!("ROMAN.TTF".parse::<Font>().unwrap(), Font::FontRoman);
+        assert_eq!("3".parse::<Font>().unwrap(), Font::Font16x24);
+        assert_eq!(Font::FontRoman.to_string(), "ROMAN.TTF");
+
+        // TrueType fonts take point sizes, other downloaded fonts multipliers.
+        let ttf = Font::Custom("arial.ttf".to_owned());
+        let bitmap = Font::Custom("MYFONT.FNT".to_owned());
+        assert!(ttf.is_scalable() && !bitmap.is_scalable());
+        let mut printer = printer();
+        let mut text = |font: &Font, x, y| {
+            let at = Size::Dots(0);
+            let rotation = Rotation::NoRotation;
+            printer
+                .text(at, at, font.clone(), rotation, x, y, None, "x")
+                .map(drop)
+        };
+        for (x, y) in [(1, 1), (255, 255), (11, 12)] {
+            text(&ttf, x, y).unwrap();
+        }
+        assert_eq!(invalid(text(&ttf, 0, 12)), "multiply_x");
+        assert_eq!(invalid(text(&ttf, 12, 0)), "multiply_y");
+        for (x, y) in [(1, 1), (10, 10)] {
+            text(&bitmap, x, y).unwrap();
+        }
+        assert_eq!(invalid(text(&bitmap, 11, 1)), "multiply_x");
+        assert_eq!(invalid(text(&bitmap, 1, 12)), "multiply_y");
+        assert_eq!(invalid(text(&bitmap, 0, 1)), "multiply_x");
+        let injected = Font::Custom("A\",0,0,\"B.TTF".to_owned());
+        assert_eq!(invalid(text(&injected, 12, 12)), "file name");
+    }
+
+    #[test]
+    fn block() {
+        let mut printer = printer();
+        let at = Size::Dots(0);
+        printer
+            .block(
+                at,
+                at,
+                Size::Dots(200),
+                Size::Dots(100),
+                Font::Font12x20,
+                Rotation::NoRotation,
+                1,
+                1,
+                Some(Size::Dots(4)),
+                Some(Alignment::Center),
+                Some(true),
+                "wrapped line",
+            )
+            .unwrap()
+            .block(
+                at,
+                at,
+                Size::Dots(200),
+                Size::Dots(100),
+                Font::Font12x20,
+                Rotation::NoRotation,
+                1,
+                1,
+                None,
+                None,
+                None,
+                "wrapped line",
+            )
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"BLOCK 0,0,200,100,\"2\",0,1,1,4,2,1,\"wrapped line\"\r\n\
+              BLOCK 0,0,200,100,\"2\",0,1,1,\"wrapped line\"\r\n"
+        );
+
+        let mut printer = self::printer();
+        let long = "x".repeat(4097);
+        let result = printer.block(
+            at,
+            at,
+            at,
+            at,
+            Font::Font12x20,
+            Rotation::NoRotation,
+            1,
+            1,
+            None,
+            None,
+            None,
+            &long,
+        );
+        assert_eq!(invalid(result), "content length");
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn barcode() {
+        let mut printer = printer();
+        printer
+            .barcode(
+                Size::Dots(10),
+                Size::Dots(20),
+                Barcode::Barcode128,
+                Size::Dots(50),
+                HumanReadable::ReadableAlignsToCenter,
+                Rotation::NoRotation,
+                NarrowWide::N2W5,
+                None,
+                "ABC\"1",
+            )
+            .unwrap()
+            .barcode(
+                Size::Dots(10),
+                Size::Dots(20),
+                Barcode::BarcodeEan13,
+                Size::Dots(50),
+                HumanReadable::NotReadable,
+                Rotation::Rotation180,
+                NarrowWide::N1W1,
+                Some(Alignment::Center),
+                "4006381333931",
+            )
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"BARCODE 10,20,\"128\",50,2,0,2,5, \"ABC\\[\"]1\"\r\n\
+              BARCODE 10,20,\"EAN13\",50,0,180,1,1,2, \"400638133393\"\r\n"
+        );
+
+        let mut printer = self::printer();
+        let result = printer.barcode(
+            Size::Dots(0),
+            Size::Dots(0),
+            Barcode::BarcodeEan13,
+            Size::Dots(50),
+            HumanReadable::NotReadable,
+            Rotation::NoRotation,
+            NarrowWide::N1W1,
+            None,
+            "4006381333932",
+        );
+        assert!(matches!(result, Err(Error::InvalidBarcode { .. })));
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn qrcode() {
+        let mut printer = printer();
+        printer
+            .qrcode(
+                Size::Dots(10),
+                Size::Dots(10),
+                10,
+                4,
+                Rotation::Rotation90,
+                Some(QrCodeJustification::UpperLeft),
+                "https://example.com",
+            )
+            .unwrap()
+            .qrcode(
+                Size::Dots(10),
+                Size::Dots(10),
+                30,
+                10,
+                Rotation::NoRotation,
+                None,
+                "x",
+            )
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"QRCODE 10,10,M,4,A,90,J1,\"https://example.com\"\r\nQRCODE 10,10,H,10,A,0,\"x\"\r\n"
+        );
+
+        let mut printer = self::printer();
+        let at = Size::Dots(0);
+        let rotation = Rotation::NoRotation;
+        assert_eq!(
+            invalid(printer.qrcode(at, at, 0, 0, rotation, None, "x")),
+            "cellwidth"
+        );
+        assert_eq!(
+            invalid(printer.qrcode(at, at, 0, 11, rotation, None, "x")),
+            "cellwidth"
+        );
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn data_matrix() {
+        let mut printer = printer();
+        printer
+            .data_matrix(
+                Size::Dots(10),
+                Size::Dots(110),
+                Size::Dots(400),
+                Size::Dots(400),
+                Some('~'),
+                Some(Size::Dots(6)),
+                Some(Rotation::Rotation90),
+                Some(true),
+                Some(16),
+                Some(48),
+                "DMATRIX",
+            )
+            .unwrap()
+            .data_matrix(
+                Size::Dots(10),
+                Size::Dots(110),
+                Size::Dots(400),
+                Size::Dots(400),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                "x",
+            )
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"DMATRIX 10,110,400,400,c126,x6,r90,a1,16,48, \"DMATRIX\"\r\nDMATRIX 10,110,400,400, \"x\"\r\n"
+        );
+
+        let mut printer = self::printer();
+        let at = Size::Dots(0);
+        let mut data_matrix = |escape, row, col| {
+            printer
+                .data_matrix(at, at, at, at, escape, None, None, None, row, col, "x")
+                .map(|_| ())
+        };
+        assert_eq!(invalid(data_matrix(Some('é'), None, None)), "escape_symbol");
+        assert_eq!(invalid(data_matrix(None, Some(9), None)), "row_size");
+        assert_eq!(invalid(data_matrix(None, Some(145), None)), "row_size");
+        assert_eq!(invalid(data_matrix(None, None, Some(9))), "col_size");
+        assert_eq!(invalid(data_matrix(None, None, Some(145))), "col_size");
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn rss() {
+        let mut printer = printer();
+        let at = Size::Dots(10);
+        let rotation = Rotation::NoRotation;
+        printer
+            .rss(
+                at,
+                at,
+                RssType::Rss14,
+                rotation,
+                Size::Dots(2),
+                1,
+                None,
+                None,
+                "1234567890123",
+            )
+            .unwrap()
+            .rss(
+                at,
+                at,
+                RssType::RssExp,
+                rotation,
+                Size::Dots(2),
+                2,
+                Some(4),
+                None,
+                "(01)1",
+            )
+            .unwrap()
+            .rss(
+                at,
+                at,
+                RssType::Ucc128Cca,
+                rotation,
+                Size::Dots(2),
+                1,
+                None,
+                Some(25),
+                "1",
+            )
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"RSS 10,10, \"RSS14\",0,2,1, \"1234567890123\"\r\n\
+              RSS 10,10, \"RSSEXP\",0,2,2,4, \"(01)1\"\r\n\
+              RSS 10,10, \"UCC128CCA\",0,2,1,25, \"1\"\r\n"
+        );
+
+        let mut printer = self::printer();
+        let mut rss = |rss_type, module_width, separator, seg_width, lin_height| {
+            printer
+                .rss(
+                    at,
+                    at,
+                    rss_type,
+                    rotation,
+                    Size::Dots(module_width),
+                    separator,
+                    seg_width,
+                    lin_height,
+                    "1",
+                )
+                .map(|_| ())
+        };
+        assert_eq!(
+            invalid(rss(RssType::Rss14, 0, 1, None, None)),
+            "module_width"
+        );
+        assert_eq!(
+            invalid(rss(RssType::Rss14, 11, 1, None, None)),
+            "module_width"
+        );
+        assert_eq!(
+            invalid(rss(RssType::Rss14, 2, 3, None, None)),
+            "separator_height"
+        );
+        assert_eq!(invalid(rss(RssType::RssExp, 2, 1, None, None)), "seg_width");
+        assert_eq!(
+            invalid(rss(RssType::RssExp, 2, 1, Some(1), None)),
+            "seg_width"
+        );
+        assert_eq!(
+            invalid(rss(RssType::RssExp, 2, 1, Some(23), None)),
+            "seg_width"
+        );
+        assert_eq!(
+            invalid(rss(RssType::Ucc128Ccc, 2, 1, None, None)),
+            "lin_height"
+        );
+        assert_eq!(
+            invalid(rss(RssType::Ucc128Cca, 2, 1, None, Some(0))),
+            "lin_height"
+        );
+        assert_eq!(
+            invalid(rss(RssType::Ucc128Cca, 2, 1, None, Some(501))),
+            "lin_height"
+        );
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn two_dimensional_codes() {
+        let mut printer = printer();
+        let at = Size::Dots(10);
+        let rotation = Rotation::NoRotation;
+        printer
+            .pdf417(at, at, Size::Dots(400), Size::Dots(200), rotation, "PDF")
+            .unwrap()
+            .mpdf417(at, at, rotation, None, None, Some(5), "MPDF")
+            .unwrap()
+            .codablock(at, at, rotation, None, Some(Size::Dots(2)), "CODA")
+            .unwrap()
+            .aztec(at, at, rotation, 6, 0, false, false, 1, false, "AZ")
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"PDF417 10,10,400,200,0,\"PDF\"\r\n\
+              MPDF417 10,10,0,1,10,0, \"MPDF\"\r\n\
+              CODABLOCK 10,10,0,8,2,\"CODA\"\r\n\
+              AZTEC 10,10,0,6,0,0,0,1,0,2,AZ\r\n"
+        );
+
+        let mut printer = self::printer();
+        let mut aztec = |size, ecp, multi| {
+            printer
+                .aztec(at, at, rotation, size, ecp, false, false, multi, false, "x")
+                .map(|_| ())
+        };
+        assert_eq!(invalid(aztec(0, 0, 1)), "size");
+        assert_eq!(invalid(aztec(21, 0, 1)), "size");
+        assert_eq!(invalid(aztec(1, 301, 1)), "ecp");
+        assert_eq!(invalid(aztec(1, 0, 27)), "multi");
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn injected_content() {
+        let mut printer = printer();
+        let at = Size::Dots(10);
+        let rotation = Rotation::NoRotation;
+        let injected = "x\"\r\nPRINT 1000\r\nCLS\r\nTEXT 0,0,\"1\",0,1,1,\"y";
+        for content in [injected, "x\x1d\"\r\nPRINT 1", "x\x1b!R"] {
+            assert_eq!(
+                invalid(printer.qrcode(at, at, 10, 4, rotation, None, content)),
+                "content"
+            );
+            assert_eq!(
+                invalid(printer.pdf417(at, at, at, at, rotation, content)),
+                "content"
+            );
+            assert_eq!(
+                invalid(
+                    printer
+                        .data_matrix(at, at, at, at, None, None, None, None, None, None, content)
+                ),
+                "content"
+            );
+            assert_eq!(
+                invalid(printer.codablock(at, at, rotation, None, None, content)),
+                "content"
+            );
+            assert_eq!(
+                invalid(printer.tlc39(
+                    at, at, rotation, None, None, None, None, None, "123456", "123", content
+                )),
+                "content"
+            );
+            assert_eq!(
+                invalid(
+                    printer
+                        .tlc39(at, at, rotation, None, None, None, None, None, content, "123", "x")
+                ),
+                "content"
+            );
+        }
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn gs1_separators() {
+        let mut printer = printer();
+        let at = Size::Dots(10);
+        let rotation = Rotation::NoRotation;
+        let gs1 = "0109501101530003\x1d10ABC\x1e";
+        printer
+            .qrcode(at, at, 10, 4, rotation, None, gs1)
+            .unwrap()
+            .data_matrix(at, at, at, at, None, None, None, None, None, None, gs1)
+            .unwrap()
+            .barcode(
+                at,
+                at,
+                Barcode::Barcode128,
+                at,
+                HumanReadable::NotReadable,
+                rotation,
+                NarrowWide::N1W1,
+                None,
+                gs1,
+            )
+            .unwrap();
+        let commands = String::from_utf8(printer.take_bytes()).unwrap();
+        assert_eq!(commands.matches(gs1).count(), 3, "{commands:?}");
+
+        // Other symbologies and text keep rejecting them.
+        assert_eq!(
+            invalid(printer.pdf417(at, at, at, at, rotation, gs1)),
+            "content"
+        );
+        assert_eq!(
+            invalid(printer.text(at, at, Font::Font8x12, rotation, 1, 1, None, gs1)),
+            "content"
+        );
+        assert!(printer.bytes().is_empty());
+    }
+
+    #[test]
+    fn bitmap() {
+        let mut printer = printer();
+        printer
+            .bitmap(
+                Size::Dots(1),
+                Size::Dots(2),
+                1,
+                2,
+                BitmapMode::Or,
+                vec![0xff, 0x00],
+            )
+            .unwrap()
+            .bitmap(
+                Size::Dots(1),
+                Size::Dots(2),
+                1,
+                2,
+                BitmapMode::Overwrite,
+                vec![0x0f, 0xf0],
+            )
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"BITMAP 1,2,1,2,1,\xff\x00\r\nBITMAP 1,2,1,2,0,\x0f\xf0\r\n"
+        );
+
+        let mut printer = self::printer();
+        printer
+            .bitmap(
+                Size::Dots(0),
+                Size::Dots(0),
+                10,
+                100,
+                BitmapMode::Overwrite,
+                vec![0xff; 1000],
+            )
+            .unwrap();
+        let bytes = printer.take_bytes();
+        let data = bytes
+            .strip_prefix(b"BITMAP 0,0,10,100,3,")
+            .and_then(|data| data.strip_suffix(b"\r\n"))
+            .expect("compressed BITMAP");
+        let comma = data.iter().position(|&b| b == b',').unwrap();
+        let length: usize = std::str::from_utf8(&data[..comma])
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(length, data.len() - comma - 1);
+        assert_eq!(
+            miniz_oxide::inflate::decompress_to_vec_zlib(&data[comma + 1..]).unwrap(),
+            vec![0xff; 1000]
+        );
+
+        printer.set_bitmap_compression(false);
+        printer
+            .bitmap(
+                Size::Dots(0),
+                Size::Dots(0),
+                1,
+                1,
+                BitmapMode::Overwrite,
+                vec![0xff; 1000],
+            )
+            .unwrap();
+        assert!(printer.bytes().starts_with(b"BITMAP 0,0,1,1,0,\xff"));
+    }
+
+    #[test]
+    fn stored_images() {
+        let mut printer = printer();
+        printer
+            .put_bmp(Size::Dots(10), Size::Dots(20), "LOGO.BMP", None, None)
+            .unwrap()
+            .put_bmp(Size::Dots(0), Size::Dots(0), "LOGO.BMP", Some(1), None)
+            .unwrap()
+            .put_bmp(Size::Dots(0), Size::Dots(0), "PHOTO.BMP", Some(8), Some(60))
+            .unwrap()
+            .put_pcx(Size::Metric(1.0), Size::Dots(2), "LOGO.PCX")
+            .unwrap()
+            .put_png(Size::Dots(3), Size::Dots(4), "LOGO.PNG")
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"PUTBMP 10,20,\"LOGO.BMP\"\r\nPUTBMP 0,0,\"LOGO.BMP\",1\r\n\
+              PUTBMP 0,0,\"PHOTO.BMP\",8,60\r\nPUTPCX 7,2,\"LOGO.PCX\"\r\n\
+              PUTPNG 3,4,\"LOGO.PNG\"\r\n"
+        );
+
+        let written = printer.bytes().len();
+        let origin = || (Size::Dots(0), Size::Dots(0));
+        let (x, y) = origin();
+        assert_eq!(
+            invalid(printer.put_bmp(x, y, "A.BMP", Some(4), None)),
+            "bpp"
+        );
+        let (x, y) = origin();
+        assert_eq!(
+            invalid(printer.put_bmp(x, y, "A.BMP", Some(8), Some(101))),
+            "contrast"
+        );
+        let (x, y) = origin();
+        assert_eq!(
+            invalid(printer.put_bmp(x, y, "A.BMP", Some(1), Some(50))),
+            "contrast"
+        );
+        let (x, y) = origin();
+        assert_eq!(invalid(printer.put_pcx(x, y, "A\"\r\nCLS")), "file name");
+        let (x, y) = origin();
+        assert_eq!(invalid(printer.put_png(x, y, "LONGNAME1.PNG")), "file name");
+        assert_eq!(printer.bytes().len(), written);
+    }
+
+    #[test]
+    fn codepage_encoded_content() {
+        let mut printer = printer();
+        printer
+            .codepage(Codepage::Codepage8Bit(Codepage8Bit::UnitedStates))
+            .unwrap()
+            .take_bytes();
+        let text = |printer: &mut Printer<Vec<u8>>, content| {
+            let at = Size::Dots(0);
+            printer
+                .text(
+                    at,
+                    at,
+                    Font::Font8x12,
+                    Rotation::NoRotation,
+                    1,
+                    1,
+                    None,
+                    content,
+                )
+                .map(|printer| printer.take_bytes())
+        };
+        assert_eq!(
+            text(&mut printer, "Größe").unwrap(),
+            b"TEXT 0,0,\"1\",0,1,1, \"Gr\x94\xe1e\"\r\n"
+        );
+        assert!(matches!(
+            text(&mut printer, "€"),
+            Err(Error::Unencodable {
+                character: '€', ..
+            })
+        ));
+        assert!(printer.bytes().is_empty());
+        printer.set_replacement(Some(b'?'));
+        assert_eq!(
+            text(&mut printer, "€").unwrap(),
+            b"TEXT 0,0,\"1\",0,1,1, \"?\"\r\n"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn size_serde() {
+        let sizes = [
+            (Size::Metric(20.0), r#""20mm""#),
+            (Size::Metric(0.25), r#""0.25mm""#),
+            (Size::Imperial(0.5), r#""0.5in""#),
+            (Size::Dots(120), r#""120dots""#),
+        ];
+        for (size, json) in sizes {
+            assert_eq!(serde_json::to_string(&size).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Size>(json).unwrap(), size);
+        }
+        assert_eq!(
+            serde_json::from_str::<Size>(r#"" 1.5 mm""#).unwrap(),
+            Size::Metric(1.5)
+        );
+        assert_eq!(
+            serde_json::from_str::<Size>(r#""2""#).unwrap(),
+            Size::Imperial(2.0)
+        );
+        assert_eq!(
+            serde_json::from_str::<Size>(r#""8dot""#).unwrap(),
+            Size::Dots(8)
+        );
+
+        for json in [
+            r#""20cm""#,
+            r#""mm""#,
+            r#""-3dots""#,
+            r#""1.5dots""#,
+            r#""""#,
+            "20",
+            "null",
+        ] {
+            assert!(serde_json::from_str::<Size>(json).is_err(), "{json}");
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{Error, Printer, Result, Size, Tape};
+use log::{debug, warn};
+use std::io::{Read, Write};
+
+/// Print resolution and print head width of a known printer model.
+#[derive(Debug, Clone)]
+pub struct KnownModel {
+    /// Model name as reported by `~!T`, without vendor prefix.
+    pub name: &'static str,
+    pub dpi: u32,
+    pub head_width: Size,
+}
+
+const fn model(name: &'static str, dpi: u32, head_width_mm: f32) -> KnownModel {
+    KnownModel {
+        name,
+        dpi,
+        head_width: Size::Metric(head_width_mm),
+    }
+}
+
+/// TSC and compatible printers with their resolution and print head width.
+pub const KNOWN_MODELS: &[KnownModel] = &[
+    model("TTP-225", 203, 54.0),
+    model("TTP-244", 203, 104.0),
+    model("TTP-244 PRO", 203, 108.0),
+    model("TTP-245", 203, 108.0),
+    model("TTP-247", 203, 108.0),
+    model("TTP-343", 300, 106.0),
+    model("TTP-345", 300, 106.0),
+    model("TTP-384M", 300, 104.0),
+    model("TTP-644M", 600, 108.0),
+    model("TDP-225", 203, 54.0),
+    model("TDP-244", 203, 104.0),
+    model("TDP-247", 203, 108.0),
+    model("TDP-345", 300, 106.0),
+    model("TE200", 203, 108.0),
+    model("TE210", 203, 108.0),
+    model("TE300", 300, 106.0),
+    model("TE310", 300, 106.0),
+    model("TX200", 203, 108.0),
+    model("TX300", 300, 106.0),
+    model("TX600", 600, 105.7),
+    model("TX210", 203, 108.0),
+    model("TX310", 300, 106.0),
+    model("TX610", 600, 105.7),
+    model("TA210", 203, 108.0),
+    model("TA310", 300, 106.0),
+    model("DA200", 203, 108.0),
+    model("DA210", 203, 108.0),
+    model("DA220", 203, 108.0),
+    model("DA300", 300, 106.0),
+    model("DA310", 300, 106.0),
+    model("DA320", 300, 106.0),
+    model("ME240", 203, 104.0),
+    model("ME340", 300, 106.0),
+    model("MB240", 203, 104.0),
+    model("MB340", 300, 106.0),
+    model("ML240P", 203, 104.0),
+    model("ML340P", 300, 106.0),
+    model("MH240", 203, 104.0),
+    model("MH340", 300, 106.0),
+    model("MH640", 600, 105.7),
+    model("MH241", 203, 104.0),
+    model("MH341", 300, 106.0),
+    model("MH641", 600, 105.7),
+    model("MX240", 203, 104.0),
+    model("MX340", 300, 106.0),
+    model("MX640", 600, 105.7),
+    model("ALPHA-2R", 203, 48.0),
+    model("ALPHA-3R", 203, 72.0),
+    model("ALPHA-4L", 203, 104.0),
+    model("XP-420B", 203, 108.0),
+    model("XP-470B", 203, 108.0),
+    model("XP-365B", 203, 80.0),
+];
+
+/// Finds the model whose name is contained in `name`, ignoring case.
+/// If several models match, the longest name wins, so `TTP-244 Pro` is not mistaken for `TTP-244`.
+pub fn lookup_model(name: &str) -> Option<&'static KnownModel> {
+    find_model(KNOWN_MODELS, name)
+}
+
+fn find_model(models: &'static [KnownModel], name: &str) -> Option<&'static KnownModel> {
+    let name = name.to_uppercase();
+    models
+        .iter()
+        .filter(|model| name.contains(model.name))
+        .max_by_key(|model| model.name.len())
+}
+
+impl<T: Read + Write> Printer<T> {
+    /// Create a new printer, taking the resolution from the model name reported by the printer.
+    /// `fallback_dpi` is used when the model can't be queried or isn't one of [`KNOWN_MODELS`].
+    pub fn with_detected_resolution(
+        transport: T,
+        tape: Tape,
+        fallback_dpi: Option<u32>,
+    ) -> Result<Self> {
+        let mut printer = Printer::unconfigured(transport, fallback_dpi.unwrap_or(0));
+
+        let detected = match printer.model() {
+            Ok(model) => {
+                let known = lookup_model(&model.name);
+                if known.is_none() {
+                    warn!("unknown printer model {:?}", model.name);
+                }
+                known
+            }
+            Err(e) => {
+                warn!("failed to query printer model: {e}");
+                None
+            }
+        };
+
+        printer.resolution = match (detected, fallback_dpi) {
+            (Some(model), _) => {
+                debug!("detected {} with {} dpi", model.name, model.dpi);
+                model.dpi
+            }
+            (None, Some(dpi)) => dpi,
+            (None, None) => {
+                return Err(Error::invalid(
+                    "fallback_dpi",
+                    "resolution for undetected models",
+                    "None",
+                ))
+            }
+        };
+
+        printer.setup(tape)?;
+        Ok(printer)
+    }
+
+    /// Queries the model name and looks it up in [`KNOWN_MODELS`].
+    pub fn known_model(&mut self) -> Result<Option<&'static KnownModel>> {
+        Ok(lookup_model(&self.model()?.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::tests::Mock;
+
+    fn tape() -> Tape {
+        Tape {
+            width: Size::Metric(40.0),
+            height: Some(Size::Metric(30.0)),
+            gap: Size::Metric(2.0),
+            gap_offset: None,
+        }
+    }
+
+    #[test]
+    fn longest_match() {
+        static MODELS: &[KnownModel] = &[model("XT-40", 203, 104.0), model("XT-40 HD", 300, 106.0)];
+        assert_eq!(find_model(MODELS, "XT-40").unwrap().dpi, 203);
+        assert_eq!(find_model(MODELS, "Acme XT-40 HD v2").unwrap().dpi, 300);
+        assert!(find_model(MODELS, "XT-4").is_none());
+
+        let pro = lookup_model("TTP-244 Pro").unwrap();
+        assert_eq!(
+            (pro.name, pro.head_width),
+            ("TTP-244 PRO", Size::Metric(108.0))
+        );
+        assert_eq!(
+            lookup_model("TTP-244").unwrap().head_width,
+            Size::Metric(104.0)
+        );
+    }
+
+    #[test]
+    fn ignores_case() {
+        assert_eq!(lookup_model("tsc tx310").unwrap().dpi, 300);
+        assert_eq!(lookup_model("Alpha-3R").unwrap().name, "ALPHA-3R");
+        assert!(lookup_model("unknown").is_none());
+    }
+
+    #[test]
+    fn detected_resolution() {
+        let printer = Printer::with_detected_resolution(
+            Mock::printer(b"TSC MH641\r", false).into_inner(),
+            tape(),
+            Some(203),
+        )
+        .unwrap();
+        assert_eq!(printer.resolution(), 600);
+        assert_eq!(
+            printer.get_ref().written,
+            b"~!TSIZE 40 mm,30 mm\r\nGAP 2 mm\r\nCLS\r\n"
+        );
+    }
+
+    #[test]
+    fn fallback_resolution() {
+        let unknown = Mock::printer(b"Acme 1000\r", false).into_inner();
+        let printer = Printer::with_detected_resolution(unknown, tape(), Some(300)).unwrap();
+        assert_eq!(printer.resolution(), 300);
+
+        let closed = Mock::printer(b"", true).into_inner();
+        let printer = Printer::with_detected_resolution(closed, tape(), Some(300)).unwrap();
+        assert_eq!(printer.resolution(), 300);
+
+        let unknown = Mock::printer(b"Acme 1000\r", false).into_inner();
+        match Printer::with_detected_resolution(unknown, tape(), None) {
+            Err(Error::InvalidParameter { field, .. }) => assert_eq!(field, "fallback_dpi"),
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,1368 @@
+use crate::{
+    content::unescape, BitmapMode, Codepage, Country, Element, Error, Printer, Result, RssType,
+    Selftest, SerialSettings, Size, Storage,
+};
+use log::debug;
+use std::{io::Write, str::FromStr, time::Duration};
+
+/// A single TSPL command. Drawing commands are represented by the [`Element`] they produce,
+/// the others mirror the [`Printer`] method of the same name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Size {
+        width: Size,
+        height: Option<Size>,
+    },
+    Gap {
+        gap: Size,
+        offset: Option<Size>,
+    },
+    GapDetect(Option<(Size, Size)>),
+    BlineDetect(Option<(Size, Size)>),
+    AutoDetect(Option<(Size, Size)>),
+    Bline {
+        black_line_height: Size,
+        extra_feeding_len: Size,
+    },
+    Offset(Size),
+    Speed(String),
+    Density(u8),
+    Direction {
+        reversed: bool,
+        mirrored: bool,
+    },
+    Reference(Size, Size),
+    Shift {
+        x: Option<Size>,
+        y: Size,
+    },
+    Country(Country),
+    Codepage(Codepage),
+    Cls,
+    Feed(Size),
+    Backup(Size),
+    Backfeed(Size),
+    Formfeed,
+    Home,
+    Print {
+        sets: u32,
+        copies: Option<u32>,
+    },
+    Sound {
+        level: u8,
+        interval: u16,
+    },
+    Cut,
+    LimitFeed {
+        n: Size,
+        minpaper_maxgap: Option<(Size, Size)>,
+    },
+    Selftest(Selftest),
+    SetCom1(SerialSettings),
+    Eoj,
+    Delay(Duration),
+    InitialPrinter,
+    Download {
+        name: String,
+        storage: Storage,
+        data: Vec<u8>,
+    },
+    Kill {
+        name: String,
+        storage: Storage,
+    },
+    MoveToFlash,
+    /// A drawing command.
+    Draw(Element),
+    /// A line the parser has no typed representation for, kept verbatim without its line ending.
+    Raw(String),
+}
+
+/// Parses TSPL source into commands.
+///
+/// Commands are separated by CR LF or LF. Commands the driver doesn't model are returned as
+/// [`Command::Raw`], malformed known commands fail with [`Error::Parse`].
+pub fn parse(input: &[u8]) -> Result<Vec<Command>> {
+    let mut cursor = Cursor {
+        input,
+        pos: 0,
+        line: 1,
+        line_start: 0,
+        codepage: None,
+    };
+    let mut commands = Vec::new();
+
+    loop {
+        cursor.skip_spaces();
+        match cursor.peek() {
+            None => break,
+            Some(b'\r' | b'\n') => {
+                cursor.end_line()?;
+                continue;
+            }
+            _ => {}
+        }
+        let command = cursor.command()?;
+        if let Command::Codepage(codepage) = command {
+            cursor.codepage = Some(codepage);
+        }
+        // Binary payloads are often directly followed by the next command.
+        let binary = matches!(
+            command,
+            Command::Download { .. } | Command::Draw(Element::Bitmap { .. })
+        );
+        if !binary || cursor.at_eol() {
+            cursor.end_line()?;
+        }
+        commands.push(command);
+    }
+
+    Ok(commands)
+}
+
+impl<T: Write> Printer<T> {
+    /// Sends a single parsed command.
+    pub fn execute(&mut self, command: &Command) -> Result<&mut Self> {
+        match command {
+            Command::Size { width, height } => self.size(*width, *height),
+            Command::Gap { gap, offset } => self.gap(*gap, *offset),
+            Command::GapDetect(calib) => self.gap_detect(*calib),
+            Command::BlineDetect(calib) => self.bline_detect(*calib),
+            Command::AutoDetect(calib) => self.auto_detect(*calib),
+            Command::Bline {
+                black_line_height,
+                extra_feeding_len,
+            } => self.bline(*black_line_height, *extra_feeding_len),
+            Command::Offset(offset) => self.offset(*offset),
+            Command::Speed(speed) => self.speed(speed),
+            Command::Density(density) => self.density(*density),
+            Command::Direction { reversed, mirrored } => self.direction(*reversed, *mirrored),
+            Command::Reference(x, y) => self.reference(*x, *y),
+            Command::Shift { x, y } => self.shift(*x, *y),
+            Command::Country(country) => self.country(*country),
+            Command::Codepage(codepage) => self.codepage(*codepage),
+            Command::Cls => self.cls(),
+            Command::Feed(feed) => self.feed(*feed),
+            Command::Backup(feed) => self.backup(*feed),
+            Command::Backfeed(feed) => self.backfeed(*feed),
+            Command::Formfeed => self.formfeed(),
+            Command::Home => self.home(),
+            Command::Print { sets, copies } => self.print(*sets, *copies),
+            Command::Sound { level, interval } => self.sound(*level, *interval),
+            Command::Cut => self.cut(),
+            Command::LimitFeed { n, minpaper_maxgap } => self.limit_feed(*n, *minpaper_maxgap),
+            Command::Selftest(kind) => self.selftest(*kind),
+            Command::SetCom1(settings) => self.set_com1(*settings),
+            Command::Eoj => self.eoj(),
+            Command::Delay(delay) => self.delay(*delay),
+            Command::InitialPrinter => self.initial_printer(),
+            Command::Download {
+                name,
+                storage,
+                data,
+            } => self.download(name, data, *storage),
+            Command::Kill { name, storage } => self.kill(name, *storage),
+            Command::MoveToFlash => self.move_to_flash(),
+            Command::Draw(element) => self.draw(element),
+            Command::Raw(line) => {
+                let cmd = format!("{line}\r\n");
+                debug!("{cmd}");
+                self.transport.write_all(cmd.as_bytes())?;
+                Ok(self)
+            }
+        }
+    }
+}
+
+/// Position of the parser in the source.
+struct Cursor<'a> {
+    input: &'a [u8],
+    pos: usize,
+    line: usize,
+    line_start: usize,
+    /// Codepage of quoted text, once selected with `CODEPAGE`.
+    codepage: Option<Codepage>,
+}
+
+enum Value {
+    Bare(String),
+    Quoted(String),
+}
+
+/// A command argument with the position it starts at.
+struct Arg {
+    value: Value,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn column(&self) -> usize {
+        self.pos - self.line_start + 1
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error::Parse {
+            line: self.line,
+            column: self.column(),
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn at_eol(&self) -> bool {
+        matches!(self.peek(), None | Some(b'\r' | b'\n'))
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes the line ending, failing if anything but spaces is left on the line.
+    fn end_line(&mut self) -> Result<()> {
+        self.skip_spaces();
+        match self.peek() {
+            None => return Ok(()),
+            Some(b'\r') => {
+                self.pos += 1;
+                if self.peek() == Some(b'\n') {
+                    self.pos += 1;
+                }
+            }
+            Some(b'\n') => self.pos += 1,
+            Some(_) => return Err(self.error("expected end of line")),
+        }
+        self.line += 1;
+        self.line_start = self.pos;
+        Ok(())
+    }
+
+    fn text(&self, bytes: &'a [u8], column: usize) -> Result<&'a str> {
+        std::str::from_utf8(bytes).map_err(|e| Error::Parse {
+            line: self.line,
+            column: column + e.valid_up_to(),
+            message: "invalid UTF-8".to_owned(),
+        })
+    }
+
+    /// Decodes quoted text written in the active codepage.
+    fn content(&self, bytes: &'a [u8], column: usize) -> Result<String> {
+        match self.codepage {
+            Some(codepage) => codepage
+                .decode(bytes)
+                .map(|text| text.into_owned())
+                .ok_or_else(|| Error::Parse {
+                    line: self.line,
+                    column,
+                    message: format!("invalid text for codepage {codepage}"),
+                }),
+            None => self.text(bytes, column).map(str::to_owned),
+        }
+    }
+
+    /// Reads bytes up to the next space, comma or line end.
+    fn word(&mut self) -> Result<&'a str> {
+        let (start, column) = (self.pos, self.column());
+        while !self.at_eol() && !matches!(self.peek(), Some(b' ' | b'\t' | b',')) {
+            self.pos += 1;
+        }
+        self.text(&self.input[start..self.pos], column)
+    }
+
+    fn comma(&mut self) -> Result<()> {
+        self.skip_spaces();
+        if self.peek() != Some(b',') {
+            return Err(self.error("expected ','"));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let data = self
+            .input
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| self.error(format!("expected {len} bytes of data")))?;
+        self.pos += len;
+        Ok(data)
+    }
+
+    fn arg(&mut self) -> Result<Arg> {
+        self.skip_spaces();
+        let (line, column) = (self.line, self.column());
+
+        let value = if self.peek() == Some(b'"') {
+            self.pos += 1;
+            let start = self.pos;
+            loop {
+                match self.peek() {
+                    Some(b'"') => break,
+                    Some(b'\\') if self.input[self.pos..].starts_with(b"\\[\"]") => self.pos += 4,
+                    Some(b'\r' | b'\n') | None => {
+                        return Err(Error::Parse {
+                            line,
+                            column,
+                            message: "unterminated string".to_owned(),
+                        })
+                    }
+                    Some(_) => self.pos += 1,
+                }
+            }
+            let content = self.content(&self.input[start..self.pos], column + 1)?;
+            self.pos += 1;
+            Value::Quoted(unescape(content))
+        } else {
+            let start = self.pos;
+            while !self.at_eol() && self.peek() != Some(b',') {
+                self.pos += 1;
+            }
+            Value::Bare(
+                self.text(&self.input[start..self.pos], column)?
+                    .trim_end()
+                    .to_owned(),
+            )
+        };
+
+        self.skip_spaces();
+        if !self.at_eol() && self.peek() != Some(b',') {
+            return Err(self.error("expected ',' or end of line"));
+        }
+        Ok(Arg {
+            value,
+            line,
+            column,
+        })
+    }
+
+    /// Reads the comma separated arguments up to the end of the line.
+    fn args(&mut self) -> Result<Vec<Arg>> {
+        let mut args = Vec::new();
+        self.skip_spaces();
+        if self.at_eol() {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.arg()?);
+            if self.at_eol() {
+                return Ok(args);
+            }
+            self.comma()?;
+        }
+    }
+
+    /// Reads `count` arguments, each followed by a comma.
+    fn header(&mut self, count: usize) -> Result<Vec<Arg>> {
+        (0..count)
+            .map(|_| {
+                let arg = self.arg()?;
+                self.comma()?;
+                Ok(arg)
+            })
+            .collect()
+    }
+
+    fn command(&mut self) -> Result<Command> {
+        let (start, column) = (self.pos, self.column());
+        let name = self.word()?.to_ascii_uppercase();
+        let at = Position {
+            line: self.line,
+            column,
+        };
+
+        let command = match name.as_str() {
+            "SET" => {
+                self.skip_spaces();
+                if self.word()?.eq_ignore_ascii_case("COM1") {
+                    let args = self.args()?;
+                    at.arity("SET COM1", &args, 4..=4)?;
+                    Command::SetCom1(SerialSettings {
+                        baud_rate: args[0].parse("baud rate")?,
+                        parity: args[1].parse("parity")?,
+                        data_bits: args[2].parse("data bits")?,
+                        stop_bits: args[3].parse("stop bits")?,
+                    })
+                } else {
+                    self.raw(start)?
+                }
+            }
+            "BITMAP" => {
+                let args = self.header(5)?;
+                let width_bytes: u16 = args[2].number()?;
+                let height_dots: u16 = args[3].number()?;
+                let len = width_bytes as usize * height_dots as usize;
+                // Mode 3 carries zlib compressed data for an overwriting bitmap.
+                let (mode, data) = if args[4].bare()? == "3" {
+                    let compressed = self.arg()?;
+                    self.comma()?;
+                    let (line, column) = (self.line, self.column());
+                    let data = self.take(compressed.number()?)?;
+                    let data = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, len)
+                        .map_err(|e| Error::Parse {
+                        line,
+                        column,
+                        message: format!("malformed compressed bitmap: {e}"),
+                    })?;
+                    (BitmapMode::Overwrite, data)
+                } else {
+                    (args[4].parse("bitmap mode")?, self.take(len)?.to_vec())
+                };
+                Command::Draw(Element::Bitmap {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    width_bytes,
+                    height_dots,
+                    mode,
+                    data,
+                })
+            }
+            "DOWNLOAD" => {
+                let first = self.arg()?;
+                let (storage, name) = match &first.value {
+                    Value::Bare(s) if s.eq_ignore_ascii_case("F") => {
+                        self.comma()?;
+                        (Storage::Flash, self.arg()?)
+                    }
+                    Value::Quoted(_) => (Storage::Dram, first),
+                    Value::Bare(_) => return self.raw(start),
+                };
+                // Without a size a BASIC program follows as plain commands.
+                if self.at_eol() {
+                    return self.raw(start);
+                }
+                self.comma()?;
+                let size = self.arg()?;
+                self.comma()?;
+                let data = self.take(size.number()?)?;
+                Command::Download {
+                    name: name.quoted()?.to_owned(),
+                    storage,
+                    data: data.to_vec(),
+                }
+            }
+            "AZTEC" => {
+                let args = self.header(9)?;
+                let content = match self.arg()? {
+                    Arg {
+                        value: Value::Quoted(content),
+                        ..
+                    } => content,
+                    len => {
+                        self.comma()?;
+                        let column = self.column();
+                        let data = self.take(len.number()?)?;
+                        self.text(data, column)?.to_owned()
+                    }
+                };
+                Command::Draw(Element::Aztec {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    rotate: args[2].parse("rotation")?,
+                    size: args[3].number()?,
+                    ecp: args[4].number()?,
+                    flg: args[5].flag()?,
+                    menu: args[6].flag()?,
+                    multi: args[7].number()?,
+                    reversed: args[8].flag()?,
+                    content,
+                })
+            }
+            _ if name.starts_with(['~', '\x1b']) => self.raw(start)?,
+            _ => match self.args() {
+                Ok(args) => match at.command(&name, &args)? {
+                    Some(command) => command,
+                    None => self.raw(start)?,
+                },
+                // Unknown commands may use any syntax, only known ones have to be well-formed.
+                Err(e) => match at.command(&name, &[]) {
+                    Ok(None) => self.raw(start)?,
+                    _ => return Err(e),
+                },
+            },
+        };
+        Ok(command)
+    }
+
+    /// Keeps the line from `start` to its end verbatim.
+    fn raw(&mut self, start: usize) -> Result<Command> {
+        let column = start - self.line_start + 1;
+        self.pos = start;
+        while !self.at_eol() {
+            self.pos += 1;
+        }
+        let line = self.text(&self.input[start..self.pos], column)?;
+        Ok(Command::Raw(line.trim_end().to_owned()))
+    }
+}
+
+/// Position of a command name, used to report errors about the whole command.
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    fn arity(
+        &self,
+        name: &str,
+        args: &[Arg],
+        expected: std::ops::RangeInclusive<usize>,
+    ) -> Result<()> {
+        if expected.contains(&args.len()) {
+            return Ok(());
+        }
+        let expected = if expected.start() == expected.end() {
+            expected.start().to_string()
+        } else {
+            format!("{} to {}", expected.start(), expected.end())
+        };
+        Err(Error::Parse {
+            line: self.line,
+            column: self.column,
+            message: format!("{name} takes {expected} arguments, found {}", args.len()),
+        })
+    }
+
+    /// Builds the command `name` from its arguments, or `None` if the command isn't modeled.
+    fn command(&self, name: &str, args: &[Arg]) -> Result<Option<Command>> {
+        let optional_pair = |args: &[Arg]| -> Result<Option<(Size, Size)>> {
+            match args {
+                [] => Ok(None),
+                [x, y] => Ok(Some((x.dots()?, y.dots()?))),
+                _ => Err(self.arity_error(name, args, "0 or 2")),
+            }
+        };
+
+        let command = match name {
+            "SIZE" => {
+                self.arity(name, args, 1..=2)?;
+                Command::Size {
+                    width: args[0].size()?,
+                    height: args.get(1).map(Arg::size).transpose()?,
+                }
+            }
+            "GAP" => {
+                self.arity(name, args, 1..=2)?;
+                Command::Gap {
+                    gap: args[0].size()?,
+                    offset: args.get(1).map(Arg::size).transpose()?,
+                }
+            }
+            "GAPDETECT" => Command::GapDetect(optional_pair(args)?),
+            "BLINEDETECT" => Command::BlineDetect(optional_pair(args)?),
+            "AUTODETECT" => Command::AutoDetect(optional_pair(args)?),
+            "BLINE" => {
+                self.arity(name, args, 2..=2)?;
+                Command::Bline {
+                    black_line_height: args[0].size()?,
+                    extra_feeding_len: args[1].size()?,
+                }
+            }
+            "OFFSET" => {
+                self.arity(name, args, 1..=1)?;
+                Command::Offset(args[0].size()?)
+            }
+            "SPEED" => {
+                self.arity(name, args, 1..=1)?;
+                Command::Speed(args[0].bare()?.to_owned())
+            }
+            "DENSITY" => {
+                self.arity(name, args, 1..=1)?;
+                Command::Density(args[0].number()?)
+            }
+            "DIRECTION" => {
+                self.arity(name, args, 1..=2)?;
+                Command::Direction {
+                    reversed: args[0].flag()?,
+                    mirrored: args.get(1).map(Arg::flag).transpose()?.unwrap_or(false),
+                }
+            }
+            "REFERENCE" => {
+                self.arity(name, args, 2..=2)?;
+                Command::Reference(args[0].dots()?, args[1].dots()?)
+            }
+            "SHIFT" => {
+                self.arity(name, args, 1..=2)?;
+                match args {
+                    [y] => Command::Shift {
+                        x: None,
+                        y: y.dots()?,
+                    },
+                    [x, y] => Command::Shift {
+                        x: Some(x.dots()?),
+                        y: y.dots()?,
+                    },
+                    _ => unreachable!(),
+                }
+            }
+            "COUNTRY" => {
+                self.arity(name, args, 1..=1)?;
+                let code: u16 = args[0].number()?;
+                Command::Country(
+                    code.to_string()
+                        .parse()
+                        .map_err(|_| args[0].error(format!("unknown country code {code}")))?,
+                )
+            }
+            "CODEPAGE" => {
+                self.arity(name, args, 1..=1)?;
+                Command::Codepage(args[0].codepage()?)
+            }
+            "CLS" => {
+                self.arity(name, args, 0..=0)?;
+                Command::Cls
+            }
+            "FEED" => {
+                self.arity(name, args, 1..=1)?;
+                Command::Feed(args[0].dots()?)
+            }
+            "BACKUP" => {
+                self.arity(name, args, 1..=1)?;
+                Command::Backup(args[0].dots()?)
+            }
+            "BACKFEED" => {
+                self.arity(name, args, 1..=1)?;
+                Command::Backfeed(args[0].dots()?)
+            }
+            "FORMFEED" => {
+                self.arity(name, args, 0..=0)?;
+                Command::Formfeed
+            }
+            "HOME" => {
+                self.arity(name, args, 0..=0)?;
+                Command::Home
+            }
+            "PRINT" => {
+                self.arity(name, args, 1..=2)?;
+                Command::Print {
+                    sets: args[0].number()?,
+                    copies: args.get(1).map(Arg::number).transpose()?,
+                }
+            }
+            "SOUND" => {
+                self.arity(name, args, 2..=2)?;
+                Command::Sound {
+                    level: args[0].number()?,
+                    interval: args[1].number()?,
+                }
+            }
+            "CUT" => {
+                self.arity(name, args, 0..=0)?;
+                Command::Cut
+            }
+            "LIMITFEED" => match args {
+                [n] => Command::LimitFeed {
+                    n: n.size()?,
+                    minpaper_maxgap: None,
+                },
+                [n, minpaper, maxgap] => Command::LimitFeed {
+                    n: n.size()?,
+                    minpaper_maxgap: Some((minpaper.size()?, maxgap.size()?)),
+                },
+                _ => return Err(self.arity_error(name, args, "1 or 3")),
+            },
+            "SELFTEST" => {
+                self.arity(name, args, 0..=1)?;
+                Command::Selftest(match args.first() {
+                    Some(arg) => arg.parse("self-test page")?,
+                    None => Selftest::All,
+                })
+            }
+            "EOJ" => {
+                self.arity(name, args, 0..=0)?;
+                Command::Eoj
+            }
+            "DELAY" => {
+                self.arity(name, args, 1..=1)?;
+                Command::Delay(Duration::from_millis(args[0].number()?))
+            }
+            "INITIALPRINTER" => {
+                self.arity(name, args, 0..=0)?;
+                Command::InitialPrinter
+            }
+            "KILL" => match args {
+                [file] => Command::Kill {
+                    name: file.quoted()?.to_owned(),
+                    storage: Storage::Dram,
+                },
+                [storage, file] if storage.bare()?.eq_ignore_ascii_case("F") => Command::Kill {
+                    name: file.quoted()?.to_owned(),
+                    storage: Storage::Flash,
+                },
+                _ => return Ok(None),
+            },
+            "MOVE" => {
+                self.arity(name, args, 0..=0)?;
+                Command::MoveToFlash
+            }
+            _ => match self.element(name, args)? {
+                Some(element) => Command::Draw(element),
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(command))
+    }
+
+    fn element(&self, name: &str, args: &[Arg]) -> Result<Option<Element>> {
+        let element = match name {
+            "BAR" | "REVERSE" | "ERASE" => {
+                self.arity(name, args, 4..=4)?;
+                let (x, y, width, height) = (
+                    args[0].dots()?,
+                    args[1].dots()?,
+                    args[2].dots()?,
+                    args[3].dots()?,
+                );
+                match name {
+                    "BAR" => Element::Bar {
+                        x,
+                        y,
+                        width,
+                        height,
+                    },
+                    "REVERSE" => Element::Reverse {
+                        x,
+                        y,
+                        width,
+                        height,
+                    },
+                    _ => Element::Erase {
+                        x,
+                        y,
+                        width,
+                        height,
+                    },
+                }
+            }
+            "BOX" => {
+                self.arity(name, args, 5..=6)?;
+                Element::Box {
+                    x_start: args[0].dots()?,
+                    y_start: args[1].dots()?,
+                    x_end: args[2].dots()?,
+                    y_end: args[3].dots()?,
+                    thickness: args[4].dots()?,
+                    radius: args.get(5).map(Arg::dots).transpose()?,
+                }
+            }
+            "CIRCLE" => {
+                self.arity(name, args, 4..=4)?;
+                Element::Circle {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    diameter: args[2].dots()?,
+                    thickness: args[3].dots()?,
+                }
+            }
+            "ELLIPSE" => {
+                self.arity(name, args, 5..=5)?;
+                Element::Ellipse {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    width: args[2].dots()?,
+                    height: args[3].dots()?,
+                    thickness: args[4].dots()?,
+                }
+            }
+            "DIAGONAL" => {
+                self.arity(name, args, 5..=5)?;
+                Element::Diagonal {
+                    x_start: args[0].dots()?,
+                    y_start: args[1].dots()?,
+                    x_end: args[2].dots()?,
+                    y_end: args[3].dots()?,
+                    thickness: args[4].dots()?,
+                }
+            }
+            "TEXT" => {
+                self.arity(name, args, 7..=8)?;
+                Element::Text {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    font: args[2].parse("font")?,
+                    rotate: args[3].parse("rotation")?,
+                    multiply_x: args[4].number()?,
+                    multiply_y: args[5].number()?,
+                    alignment: match args.len() {
+                        8 => Some(args[6].parse("alignment")?),
+                        _ => None,
+                    },
+                    content: args[args.len() - 1].quoted()?.to_owned(),
+                }
+            }
+            "BLOCK" => {
+                self.arity(name, args, 9..=12)?;
+                let extra = &args[8..args.len() - 1];
+                Element::Block {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    width: args[2].dots()?,
+                    height: args[3].dots()?,
+                    font: args[4].parse("font")?,
+                    rotate: args[5].parse("rotation")?,
+                    multiply_x: args[6].number()?,
+                    multiply_y: args[7].number()?,
+                    space: extra.first().map(Arg::dots).transpose()?,
+                    alignment: extra.get(1).map(|a| a.parse("alignment")).transpose()?,
+                    fit: extra.get(2).map(Arg::flag).transpose()?,
+                    content: args[args.len() - 1].quoted()?.to_owned(),
+                }
+            }
+            "BARCODE" => {
+                self.arity(name, args, 9..=10)?;
+                let narrow_wide = format!("{},{}", args[6].bare()?, args[7].bare()?);
+                Element::Barcode {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    code_type: args[2].parse("barcode type")?,
+                    height: args[3].dots()?,
+                    human_readable: args[4].parse("human readable")?,
+                    rotate: args[5].parse("rotation")?,
+                    narrow_wide: narrow_wide.parse().map_err(|_| {
+                        args[6].error(format!("unsupported narrow,wide {narrow_wide}"))
+                    })?,
+                    alignment: match args.len() {
+                        10 => Some(args[8].parse("alignment")?),
+                        _ => None,
+                    },
+                    content: args[args.len() - 1].quoted()?.to_owned(),
+                }
+            }
+            "QRCODE" => {
+                if args.len() < 7 {
+                    return Err(self.arity_error(name, args, "at least 7"));
+                }
+                if !args[4].bare()?.eq_ignore_ascii_case("A") {
+                    return Err(args[4].error("only the automatic QRCODE mode A is supported"));
+                }
+                let mut justification = None;
+                for option in &args[6..args.len() - 1] {
+                    match option.bare()? {
+                        // Model 2 is the default.
+                        "M2" => {}
+                        j if j.starts_with('J') => {
+                            justification = Some(option.parse("justification")?)
+                        }
+                        other => {
+                            return Err(option.error(format!("unsupported QRCODE option {other}")))
+                        }
+                    }
+                }
+                Element::QrCode {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    // Representative level of each range accepted by `Printer::qrcode`.
+                    ecc_level: match args[2].bare()? {
+                        "L" => 0,
+                        "M" => 7,
+                        "Q" => 15,
+                        "H" => 25,
+                        other => return Err(args[2].error(format!("unknown ECC level {other}"))),
+                    },
+                    cellwidth_dot: args[3].number()?,
+                    rotate: args[5].parse("rotation")?,
+                    justification,
+                    content: args[args.len() - 1].quoted()?.to_owned(),
+                }
+            }
+            "DMATRIX" => {
+                if args.len() < 5 {
+                    return Err(self.arity_error(name, args, "at least 5"));
+                }
+                let (mut escape_symbol, mut module_size, mut rotate, mut rectangular) =
+                    (None, None, None, None);
+                let mut dimensions = Vec::new();
+                for option in &args[4..args.len() - 1] {
+                    let value = option.bare()?;
+                    let number = |s: &str| {
+                        s.parse::<u32>().map_err(|_| {
+                            option.error(format!("expected a number, found {value:?}"))
+                        })
+                    };
+                    match value.split_at(value.find(|c: char| c.is_ascii_digit()).unwrap_or(0)) {
+                        ("c" | "C", n) => {
+                            escape_symbol = Some(
+                                char::from_u32(number(n)?)
+                                    .ok_or_else(|| option.error("invalid escape character"))?,
+                            )
+                        }
+                        ("x" | "X", n) => module_size = Some(Size::Dots(number(n)?)),
+                        ("r" | "R", n) => {
+                            rotate = Some(
+                                n.parse()
+                                    .map_err(|_| option.error(format!("unknown rotation {n:?}")))?,
+                            )
+                        }
+                        ("a" | "A", n) => rectangular = Some(number(n)? != 0),
+                        ("", n) if dimensions.len() < 2 => dimensions.push(
+                            u8::try_from(number(n)?)
+                                .map_err(|_| option.error(format!("{n} is out of range")))?,
+                        ),
+                        _ => {
+                            return Err(option.error(format!("unsupported DMATRIX option {value}")))
+                        }
+                    }
+                }
+                Element::DataMatrix {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    width: args[2].dots()?,
+                    height: args[3].dots()?,
+                    escape_symbol,
+                    module_size,
+                    rotate,
+                    rectangular,
+                    row_size: dimensions.first().copied(),
+                    col_size: dimensions.get(1).copied(),
+                    content: args[args.len() - 1].quoted()?.to_owned(),
+                }
+            }
+            "PDF417" => {
+                self.arity(name, args, 6..=6)?;
+                Element::Pdf417 {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    width: args[2].dots()?,
+                    height: args[3].dots()?,
+                    rotate: args[4].parse("rotation")?,
+                    content: args[5].quoted()?.to_owned(),
+                }
+            }
+            "MPDF417" => {
+                self.arity(name, args, 4..=7)?;
+                let (mut module_width, mut module_height, mut col_num) = (None, None, None);
+                for (n, option) in args[3..args.len() - 1].iter().enumerate() {
+                    let value = option.bare()?;
+                    // Options are either positional or prefixed with W, H or C.
+                    let (kind, number) = match value.bytes().next() {
+                        Some(b'W' | b'w') => (0, &value[1..]),
+                        Some(b'H' | b'h') => (1, &value[1..]),
+                        Some(b'C' | b'c') => (2, &value[1..]),
+                        _ => (n, value),
+                    };
+                    let number: u32 = number
+                        .parse()
+                        .map_err(|_| option.error(format!("expected a number, found {value:?}")))?;
+                    match kind {
+                        0 => module_width = Some(Size::Dots(number)),
+                        1 => module_height = Some(Size::Dots(number)),
+                        _ => col_num = Some(number as usize),
+                    }
+                }
+                Element::MicroPdf417 {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    rotate: args[2].parse("rotation")?,
+                    module_width,
+                    module_height,
+                    col_num,
+                    content: args[args.len() - 1].quoted()?.to_owned(),
+                }
+            }
+            "CODABLOCK" => {
+                self.arity(name, args, 4..=6)?;
+                Element::Codablock {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    rotate: args[2].parse("rotation")?,
+                    row_height: args[3..args.len() - 1].first().map(Arg::dots).transpose()?,
+                    module_width: args[3..args.len() - 1].get(1).map(Arg::dots).transpose()?,
+                    content: args[args.len() - 1].quoted()?.to_owned(),
+                }
+            }
+            "RSS" => {
+                self.arity(name, args, 7..=8)?;
+                let rss_type = args[2].parse("RSS type")?;
+                let extra = args[6..args.len() - 1]
+                    .first()
+                    .map(Arg::number::<usize>)
+                    .transpose()?;
+                let (seg_width, lin_height) = match rss_type {
+                    RssType::RssExp => (extra, None),
+                    RssType::Ucc128Cca | RssType::Ucc128Ccc => (None, extra),
+                    _ => (None, None),
+                };
+                Element::Rss {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    rss_type,
+                    rotate: args[3].parse("rotation")?,
+                    module_width: args[4].dots()?,
+                    separator_height: args[5].number()?,
+                    seg_width,
+                    lin_height,
+                    content: args[args.len() - 1].quoted()?.to_owned(),
+                }
+            }
+            "TLC39" => {
+                self.arity(name, args, 4..=9)?;
+                let options = &args[3..args.len() - 1];
+                let option = |n: usize| options.get(n).map(Arg::dots).transpose();
+                let mut parts = args[args.len() - 1].quoted()?.splitn(3, ',');
+                let mut part = || parts.next().unwrap_or_default().to_owned();
+                Element::Tlc39 {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    rotate: args[2].parse("rotation")?,
+                    height: option(0)?,
+                    narrow: option(1)?,
+                    wide: option(2)?,
+                    cellwidth: option(3)?,
+                    cellheight: option(4)?,
+                    eci_number: part(),
+                    serial_number: part(),
+                    additional_data: part(),
+                }
+            }
+            "PUTBMP" => {
+                self.arity(name, args, 3..=5)?;
+                Element::PutBmp {
+                    x: args[0].dots()?,
+                    y: args[1].dots()?,
+                    name: args[2].quoted()?.to_owned(),
+                    bpp: args.get(3).map(Arg::number).transpose()?,
+                    contrast: args.get(4).map(Arg::number).transpose()?,
+                }
+            }
+            "PUTPCX" | "PUTPNG" => {
+                self.arity(name, args, 3..=3)?;
+                let (x, y, file) = (args[0].dots()?, args[1].dots()?, args[2].quoted()?);
+                match name {
+                    "PUTPCX" => Element::PutPcx {
+                        x,
+                        y,
+                        name: file.to_owned(),
+                    },
+                    _ => Element::PutPng {
+                        x,
+                        y,
+                        name: file.to_owned(),
+                    },
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(element))
+    }
+
+    fn arity_error(&self, name: &str, args: &[Arg], expected: &str) -> Error {
+        Error::Parse {
+            line: self.line,
+            column: self.column,
+            message: format!("{name} takes {expected} arguments, found {}", args.len()),
+        }
+    }
+}
+
+impl Arg {
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error::Parse {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+
+    fn bare(&self) -> Result<&str> {
+        match &self.value {
+            Value::Bare(s) => Ok(s),
+            Value::Quoted(_) => Err(self.error("expected a value, found a string")),
+        }
+    }
+
+    fn quoted(&self) -> Result<&str> {
+        match &self.value {
+            Value::Quoted(s) => Ok(s),
+            Value::Bare(s) => Err(self.error(format!("expected a quoted string, found {s:?}"))),
+        }
+    }
+
+    fn number<N: FromStr>(&self) -> Result<N> {
+        let s = self.bare()?;
+        s.parse()
+            .map_err(|_| self.error(format!("expected a number, found {s:?}")))
+    }
+
+    fn flag(&self) -> Result<bool> {
+        match self.bare()? {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            s => Err(self.error(format!("expected 0 or 1, found {s:?}"))),
+        }
+    }
+
+    fn dots(&self) -> Result<Size> {
+        self.number().map(Size::Dots)
+    }
+
+    /// A size with an optional unit, inches by default.
+    fn size(&self) -> Result<Size> {
+        let s = self.bare()?;
+        s.parse()
+            .map_err(|_| self.error(format!("expected a size, found {s:?}")))
+    }
+
+    /// A value of one of the strum enums, written either bare or quoted.
+    fn parse<E: FromStr>(&self, what: &str) -> Result<E> {
+        let s = match &self.value {
+            Value::Bare(s) | Value::Quoted(s) => s,
+        };
+        s.parse()
+            .map_err(|_| self.error(format!("unknown {what} {s:?}")))
+    }
+
+    fn codepage(&self) -> Result<Codepage> {
+        let s = self.bare()?;
+        s.parse()
+            .map(Codepage::Codepage7Bit)
+            .or_else(|_| s.parse().map(Codepage::Codepage8Bit))
+            .or_else(|_| s.parse().map(Codepage::CodepageWindows))
+            .or_else(|_| s.parse().map(Codepage::CodepageIso))
+            .map_err(|_: strum::ParseError| self.error(format!("unknown codepage {s:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Alignment, Barcode, CodepageWindows, Font, HumanReadable, NarrowWide, QrCodeJustification,
+        Rotation, Tape,
+    };
+
+    /// Parses `job` and sends the commands again.
+    fn reemit(job: &[u8]) -> Vec<u8> {
+        let mut printer = Printer::unconfigured(Vec::new(), 203);
+        for command in parse(job).unwrap() {
+            printer.execute(&command).unwrap();
+        }
+        printer.take_bytes()
+    }
+
+    fn parse_error(input: &[u8]) -> (usize, usize, String) {
+        match parse(input) {
+            Err(Error::Parse {
+                line,
+                column,
+                message,
+            }) => (line, column, message),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let tape = Tape {
+            width: Size::Metric(50.0),
+            height: Some(Size::Metric(30.0)),
+            gap: Size::Metric(2.0),
+            gap_offset: None,
+        };
+        let at = Size::Dots(10);
+        let mut printer = Printer::in_memory(tape, 203).unwrap();
+        printer
+            .density(8)
+            .unwrap()
+            .direction(true, false)
+            .unwrap()
+            .reference(Size::Dots(4), Size::Dots(8))
+            .unwrap()
+            .codepage(Codepage::CodepageWindows(CodepageWindows::Latin1))
+            .unwrap()
+            .download("LOGO.BMP", b"BM\r\n\"\0\xff", Storage::Flash)
+            .unwrap()
+            .text(
+                at,
+                at,
+                Font::Font24x32,
+                Rotation::Rotation90,
+                1,
+                2,
+                Some(Alignment::Right),
+                "Café \"Zürich\"",
+            )
+            .unwrap()
+            .block(
+                at,
+                at,
+                Size::Dots(300),
+                Size::Dots(100),
+                Font::Custom("ARIAL.TTF".to_owned()),
+                Rotation::NoRotation,
+                12,
+                12,
+                Some(Size::Dots(2)),
+                Some(Alignment::Center),
+                Some(true),
+                "wrapped, with commas",
+            )
+            .unwrap()
+            .barcode(
+                at,
+                at,
+                Barcode::BarcodeEan13,
+                Size::Dots(80),
+                HumanReadable::ReadableAlignsToCenter,
+                Rotation::NoRotation,
+                NarrowWide::N2W5,
+                Some(Alignment::Left),
+                "400638133393",
+            )
+            .unwrap()
+            .qrcode(
+                at,
+                at,
+                10,
+                4,
+                Rotation::NoRotation,
+                Some(QrCodeJustification::UpperLeft),
+                "https://example.com/?a=1,b=2",
+            )
+            .unwrap()
+            .data_matrix(
+                at,
+                at,
+                Size::Dots(200),
+                Size::Dots(200),
+                None,
+                Some(Size::Dots(6)),
+                None,
+                None,
+                Some(16),
+                Some(16),
+                "DM",
+            )
+            .unwrap()
+            .bar(at, at, Size::Dots(100), Size::Dots(2))
+            .unwrap()
+            .rectangle(
+                at,
+                at,
+                Size::Dots(90),
+                Size::Dots(90),
+                Size::Dots(3),
+                Some(Size::Dots(8)),
+            )
+            .unwrap()
+            .bitmap(at, at, 2, 2, BitmapMode::Xor, b"\r\n,\"".to_vec())
+            .unwrap()
+            .bitmap(at, at, 10, 10, BitmapMode::Overwrite, vec![0xff; 100])
+            .unwrap()
+            .put_bmp(at, at, "LOGO.BMP", Some(8), Some(50))
+            .unwrap()
+            .kill("*.BMP", Storage::Dram)
+            .unwrap()
+            .print(2, Some(1))
+            .unwrap()
+            .sound(5, 100)
+            .unwrap();
+        let job = printer.take_bytes();
+        assert_eq!(reemit(&job), job);
+    }
+
+    #[test]
+    fn binary_payloads_followed_by_commands() {
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&[0xff; 16], 6);
+        let mut job = b"BITMAP 1,2,1,2,0,\r\nBITMAP 0,0,1,1,1,\"BITMAP 0,0,1,1,2,,".to_vec();
+        job.extend_from_slice(format!("BITMAP 3,4,2,8,3,{},", compressed.len()).as_bytes());
+        job.extend_from_slice(&compressed);
+        job.extend_from_slice(b"DOWNLOAD F,\"A.BMP\",3,\r\nxDOWNLOAD \"B.BMP\",2,ab\r\nCLS\r\n");
+
+        let bitmap = |x, y, width_bytes, height_dots, mode, data: &[u8]| {
+            Command::Draw(Element::Bitmap {
+                x: Size::Dots(x),
+                y: Size::Dots(y),
+                width_bytes,
+                height_dots,
+                mode,
+                data: data.to_vec(),
+            })
+        };
+        assert_eq!(
+            parse(&job).unwrap(),
+            [
+                bitmap(1, 2, 1, 2, BitmapMode::Overwrite, b"\r\n"),
+                bitmap(0, 0, 1, 1, BitmapMode::Or, b"\""),
+                bitmap(0, 0, 1, 1, BitmapMode::Xor, b","),
+                bitmap(3, 4, 2, 8, BitmapMode::Overwrite, &[0xff; 16]),
+                Command::Download {
+                    name: "A.BMP".to_owned(),
+                    storage: Storage::Flash,
+                    data: b"\r\nx".to_vec(),
+                },
+                Command::Download {
+                    name: "B.BMP".to_owned(),
+                    storage: Storage::Dram,
+                    data: b"ab".to_vec(),
+                },
+                Command::Cls,
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_strings() {
+        let commands = parse(b"TEXT 0,0,\"3\",0,1,1,\"say \\[\"]hi\\[\"], ok\"\r\n").unwrap();
+        let [Command::Draw(Element::Text { content, font, .. })] = &commands[..] else {
+            panic!("unexpected commands {commands:?}");
+        };
+        assert_eq!(content, "say \"hi\", ok");
+        assert_eq!(*font, Font::Font16x24);
+
+        // Cyrillic in CP866 and UTF-8 once the codepage is switched.
+        let commands = parse(
+            b"CODEPAGE 866\r\nTEXT 0,0,\"1\",0,1,1,\"\x8f\xe0\xa8\xa2\xa5\xe2\"\r\n\
+              CODEPAGE UTF-8\r\nTEXT 0,0,\"1\",0,1,1,\"\xd0\x9f\xd1\x80\xd0\xb8\xd0\xb2\xd0\xb5\xd1\x82\"\r\n",
+        )
+        .unwrap();
+        let texts: Vec<&str> = commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::Draw(Element::Text { content, .. }) => Some(content.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, ["Привет", "Привет"]);
+    }
+
+    #[test]
+    fn unmodeled_commands_are_kept() {
+        let job = b"SET CUTTER OFF\r\nCLS\nSET TEAR ON\r\n";
+        assert_eq!(
+            parse(job).unwrap(),
+            [
+                Command::Raw("SET CUTTER OFF".to_owned()),
+                Command::Cls,
+                Command::Raw("SET TEAR ON".to_owned()),
+            ]
+        );
+        assert_eq!(reemit(job), b"SET CUTTER OFF\r\nCLS\r\nSET TEAR ON\r\n");
+    }
+
+    #[test]
+    fn error_positions() {
+        let (line, column, _) = parse_error(b"CLS\r\nBAR 1,2,x,4\r\n");
+        assert_eq!((line, column), (2, 9));
+        let (line, column, message) = parse_error(b"CLS\nCLS\nTEXT 0,0,\"1\",0,1,1,\"open\r\n");
+        assert_eq!(
+            (line, column, message.as_str()),
+            (3, 20, "unterminated string")
+        );
+        let (line, column, message) = parse_error(b"CLS\r\nBAR 1,2,3\r\n");
+        assert_eq!((line, column), (2, 1));
+        assert!(message.contains("takes 4"), "{message}");
+        let (line, column, message) = parse_error(b"CLS extra\r\n");
+        assert_eq!((line, column), (1, 1));
+        assert!(message.contains("takes 0"), "{message}");
+        let (line, column, _) = parse_error(b"BITMAP 0,0,2,2,0,abc");
+        assert_eq!((line, column), (1, 18));
+        let (line, column, message) =
+            parse_error(b"CODEPAGE 8859-5\r\nCLS\r\nTEXT 0,0,\"1\",0,1,1,\"\x85\"\r\n");
+        assert_eq!((line, column), (3, 21));
+        assert!(message.contains("8859-5"), "{message}");
+    }
+}
@@ -0,0 +1,735 @@
+//! Software rasterizer producing a preview of a label, or of a TSPL job, at the printer's dot resolution.
+//!
+//! Geometry (`BAR`, `BOX`, `CIRCLE`, `ELLIPSE`, `DIAGONAL`, `REVERSE`, `ERASE` and `BITMAP`) is
+//! rendered dot-exact. Text in dot fonts 1 to 8 is drawn with glyph tables of each font's cell size,
+//! and ROMAN.TTF with its advance widths, so footprint, wrapping and alignment match the printout.
+//! The letter shapes are close to the firmware fonts but not identical, see [`font`].
+//! Other fonts are drawn with font 2. Barcodes and 2D codes are not rendered.
+
+mod font;
+
+use crate::{
+    Alignment, BitmapMode, Command, Element, Error, Font, Label, Result, Rotation, Size, Tape,
+};
+use log::debug;
+use std::{io::Write, ops::Range};
+
+/// Largest canvas in dots, about 100 inches of 4 inch wide tape at 600 dpi.
+const MAX_DOTS: u64 = 1 << 28;
+
+/// Monochrome image at the printer resolution.
+///
+/// Dots are packed 8 per byte, the leftmost in the most significant bit, with rows padded to whole
+/// bytes. A set bit is a printed (black) dot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    /// Labels without a defined height grow downwards as elements are drawn.
+    grow: bool,
+    /// Something was drawn below the height a growing canvas may reach.
+    overflow: bool,
+    dots: Vec<u8>,
+}
+
+impl Canvas {
+    /// Create a blank canvas of the given size in dots.
+    /// Fails if the canvas would have more than 2^28 dots.
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        let dots = width as u64 * height as u64;
+        if dots > MAX_DOTS {
+            return Err(Error::invalid(
+                "canvas size",
+                format!("at most {MAX_DOTS} dots"),
+                format!("{width}x{height} dots"),
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            grow: false,
+            overflow: false,
+            dots: vec![0; width.div_ceil(8) as usize * height as usize],
+        })
+    }
+
+    /// Create a blank canvas covering one label of `tape`.
+    /// If the tape has no height, the canvas grows to fit the drawn elements.
+    pub fn for_tape(tape: &Tape, dpi: u32) -> Result<Self> {
+        Self::sized(tape.width, tape.height, dpi)
+    }
+
+    fn sized(width: Size, height: Option<Size>, dpi: u32) -> Result<Self> {
+        let width = width.to_dots_raw(dpi);
+        match height {
+            Some(height) => Self::new(width, height.to_dots_raw(dpi)),
+            None => Ok(Self {
+                grow: true,
+                ..Self::new(width, 0)?
+            }),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns true if the dot at `x`, `y` is printed. Dots outside the canvas are blank.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.dots[self.index(x, y)] & (0x80 >> (x % 8)) != 0
+    }
+
+    /// Packed dots of row `y`, see [`Canvas`].
+    pub fn row(&self, y: u32) -> &[u8] {
+        let stride = self.stride();
+        &self.dots[y as usize * stride..][..stride]
+    }
+
+    /// Bytes per row.
+    fn stride(&self) -> usize {
+        self.width.div_ceil(8) as usize
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.stride() + x as usize / 8
+    }
+
+    /// Draws a single label element. Fails if a canvas without defined height would grow
+    /// beyond the size accepted by [`Canvas::new`].
+    pub fn draw(&mut self, element: &Element, dpi: u32) -> Result<()> {
+        self.draw_element(element, dpi);
+        if self.overflow {
+            return Err(Error::invalid(
+                "label height",
+                format!("at most {} dots", self.max_height()),
+                "element below",
+            ));
+        }
+        Ok(())
+    }
+
+    fn draw_element(&mut self, element: &Element, dpi: u32) {
+        let d = |size: &Size| size.to_dots_raw(dpi) as i64;
+        match element {
+            Element::Bar {
+                x,
+                y,
+                width,
+                height,
+            } => self.fill(d(x), d(y), d(width), d(height), |_| true),
+            Element::Box {
+                x_start,
+                y_start,
+                x_end,
+                y_end,
+                thickness,
+                radius,
+            } => self.rounded_box(
+                (d(x_start), d(y_start)),
+                (d(x_end), d(y_end)),
+                d(thickness),
+                radius.as_ref().map(d).unwrap_or(0),
+            ),
+            Element::Circle {
+                x,
+                y,
+                diameter,
+                thickness,
+            } => self.ellipse(d(x), d(y), d(diameter), d(diameter), d(thickness)),
+            Element::Ellipse {
+                x,
+                y,
+                width,
+                height,
+                thickness,
+            } => self.ellipse(d(x), d(y), d(width), d(height), d(thickness)),
+            Element::Diagonal {
+                x_start,
+                y_start,
+                x_end,
+                y_end,
+                thickness,
+            } => self.line((d(x_start), d(y_start)), (d(x_end), d(y_end)), d(thickness)),
+            Element::Reverse {
+                x,
+                y,
+                width,
+                height,
+            } => self.fill(d(x), d(y), d(width), d(height), |dot| !dot),
+            Element::Erase {
+                x,
+                y,
+                width,
+                height,
+            } => self.fill(d(x), d(y), d(width), d(height), |_| false),
+            Element::Bitmap {
+                x,
+                y,
+                width_bytes,
+                height_dots,
+                mode,
+                data,
+            } => self.bitmap(d(x), d(y), *width_bytes, *height_dots, *mode, data),
+            Element::Text {
+                x,
+                y,
+                font,
+                rotate,
+                multiply_x,
+                multiply_y,
+                alignment,
+                content,
+            } => {
+                let face = Typeface::new(font, *multiply_x, *multiply_y, dpi);
+                let width = face.width(content);
+                let offset = match alignment {
+                    Some(Alignment::Center) => -width / 2,
+                    Some(Alignment::Right) => -width,
+                    _ => 0,
+                };
+                self.text((d(x), d(y)), *rotate, &face, (offset, 0), content);
+            }
+            Element::Block {
+                x,
+                y,
+                width,
+                height,
+                font,
+                rotate,
+                multiply_x,
+                multiply_y,
+                space,
+                alignment,
+                content,
+                ..
+            } => {
+                let face = Typeface::new(font, *multiply_x, *multiply_y, dpi);
+                let (width, height) = (d(width), d(height));
+                let line_height = face.height() + space.as_ref().map(d).unwrap_or(0);
+
+                for (n, line) in wrap(content, width, &face).iter().enumerate() {
+                    let top = n as i64 * line_height;
+                    if top + face.height() > height {
+                        break;
+                    }
+                    let line_width = face.width(line);
+                    let left = match alignment {
+                        Some(Alignment::Center) => (width - line_width) / 2,
+                        Some(Alignment::Right) => width - line_width,
+                        _ => 0,
+                    };
+                    self.text((d(x), d(y)), *rotate, &face, (left, top), line);
+                }
+            }
+            other => debug!("preview of {other:?} is not supported"),
+        }
+    }
+
+    /// Encodes the canvas as a 1 bit grayscale PNG.
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::One);
+
+        // PNG grayscale uses 1 for white.
+        let data: Vec<u8> = self.dots.iter().map(|byte| !byte).collect();
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&data))
+            .map_err(|e| Error::Io(std::io::Error::other(e)))
+    }
+
+    fn clear(&mut self) {
+        if self.grow {
+            self.height = 0;
+            self.dots.clear();
+        } else {
+            self.dots.fill(0);
+        }
+    }
+
+    /// Height up to which the canvas may hold dots.
+    fn max_height(&self) -> u32 {
+        match self.grow {
+            true => (MAX_DOTS / self.width.max(1) as u64).min(u32::MAX as u64) as u32,
+            false => self.height,
+        }
+    }
+
+    /// Part of `start..end` inside the canvas, horizontally.
+    fn columns(&self, start: i64, end: i64) -> Range<i64> {
+        start.max(0)..end.min(self.width as i64)
+    }
+
+    /// Part of `start..end` inside the canvas, vertically. Nothing if a growing canvas can't reach `end`.
+    fn rows(&mut self, start: i64, end: i64) -> Range<i64> {
+        let max = self.max_height() as i64;
+        if self.grow && end > max && start < end {
+            self.overflow = true;
+            return 0..0;
+        }
+        start.max(0)..end.min(max)
+    }
+
+    fn update(&mut self, x: i64, y: i64, f: impl Fn(bool) -> bool) {
+        if x < 0 || y < 0 || x >= self.width as i64 {
+            return;
+        }
+        if y >= self.height as i64 {
+            if !self.grow {
+                return;
+            }
+            if y >= self.max_height() as i64 {
+                self.overflow = true;
+                return;
+            }
+            self.height = y as u32 + 1;
+            self.dots.resize(self.stride() * self.height as usize, 0);
+        }
+        let (index, bit) = (self.index(x as u32, y as u32), 0x80 >> (x % 8));
+        let dot = &mut self.dots[index];
+        match f(*dot & bit != 0) {
+            true => *dot |= bit,
+            false => *dot &= !bit,
+        }
+    }
+
+    fn fill(&mut self, x: i64, y: i64, width: i64, height: i64, f: impl Fn(bool) -> bool) {
+        for py in self.rows(y, y.saturating_add(height)) {
+            for px in self.columns(x, x.saturating_add(width)) {
+                self.update(px, py, &f);
+            }
+        }
+    }
+
+    fn rounded_box(&mut self, start: (i64, i64), end: (i64, i64), thickness: i64, radius: i64) {
+        let inside = |px: i64, py: i64, (x0, y0): (i64, i64), (x1, y1): (i64, i64), r: i64| {
+            let (cx, cy) = (px as f32 + 0.5, py as f32 + 0.5);
+            if cx < x0 as f32 || cy < y0 as f32 || cx > x1 as f32 || cy > y1 as f32 {
+                return false;
+            }
+            let r = r.min((x1 - x0) / 2).min((y1 - y0) / 2).max(0) as f32;
+            let nx = cx.clamp(x0 as f32 + r, x1 as f32 - r);
+            let ny = cy.clamp(y0 as f32 + r, y1 as f32 - r);
+            (cx - nx).powi(2) + (cy - ny).powi(2) <= r * r
+        };
+        let inner_start = (start.0 + thickness, start.1 + thickness);
+        let inner_end = (end.0 - thickness, end.1 - thickness);
+
+        for py in self.rows(start.1, end.1) {
+            for px in self.columns(start.0, end.0) {
+                if inside(px, py, start, end, radius)
+                    && !inside(px, py, inner_start, inner_end, radius - thickness)
+                {
+                    self.update(px, py, |_| true);
+                }
+            }
+        }
+    }
+
+    fn ellipse(&mut self, x: i64, y: i64, width: i64, height: i64, thickness: i64) {
+        let (a, b) = (width as f32 / 2.0, height as f32 / 2.0);
+        let (ia, ib) = (a - thickness as f32, b - thickness as f32);
+        let inside = |dx: f32, dy: f32, a: f32, b: f32| {
+            a > 0.0 && b > 0.0 && (dx / a).powi(2) + (dy / b).powi(2) <= 1.0
+        };
+
+        for py in self.rows(y, y.saturating_add(height)) {
+            for px in self.columns(x, x.saturating_add(width)) {
+                let dx = px as f32 + 0.5 - (x as f32 + a);
+                let dy = py as f32 + 0.5 - (y as f32 + b);
+                if inside(dx, dy, a, b) && !inside(dx, dy, ia, ib) {
+                    self.update(px, py, |_| true);
+                }
+            }
+        }
+    }
+
+    fn line(&mut self, start: (i64, i64), end: (i64, i64), thickness: i64) {
+        let half = (thickness.max(1) as f32) / 2.0;
+        let (x0, y0) = (start.0 as f32, start.1 as f32);
+        let (dx, dy) = ((end.0 - start.0) as f32, (end.1 - start.1) as f32);
+        let len2 = dx * dx + dy * dy;
+        let margin = thickness.max(1);
+
+        let rows = self.rows(start.1.min(end.1) - margin, start.1.max(end.1) + margin + 1);
+        for py in rows {
+            for px in self.columns(start.0.min(end.0) - margin, start.0.max(end.0) + margin + 1) {
+                let (cx, cy) = (px as f32 + 0.5 - x0, py as f32 + 0.5 - y0);
+                let t = if len2 > 0.0 {
+                    ((cx * dx + cy * dy) / len2).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                if (cx - t * dx).powi(2) + (cy - t * dy).powi(2) <= half * half {
+                    self.update(px, py, |_| true);
+                }
+            }
+        }
+    }
+
+    fn bitmap(
+        &mut self,
+        x: i64,
+        y: i64,
+        width_bytes: u16,
+        height: u16,
+        mode: BitmapMode,
+        data: &[u8],
+    ) {
+        for row in 0..height as usize {
+            for col in 0..width_bytes as usize * 8 {
+                let Some(byte) = data.get(row * width_bytes as usize + col / 8) else {
+                    return;
+                };
+                // TSPL bitmaps use 0 for a printed dot.
+                let black = byte & (0x80 >> (col % 8)) == 0;
+                let (px, py) = (x + col as i64, y + row as i64);
+                match mode {
+                    BitmapMode::Overwrite => self.update(px, py, |_| black),
+                    BitmapMode::Or => self.update(px, py, |dot| dot || black),
+                    BitmapMode::Xor => self.update(px, py, |dot| dot ^ black),
+                }
+            }
+        }
+    }
+
+    /// Draws `content` with its top left corner at `offset` from `origin` before rotation.
+    fn text(
+        &mut self,
+        origin: (i64, i64),
+        rotate: Rotation,
+        face: &Typeface,
+        offset: (i64, i64),
+        content: &str,
+    ) {
+        let mut left = offset.0;
+        for c in content.chars() {
+            let advance = face.advance(c);
+            for v in 0..face.height() {
+                for u in 0..advance {
+                    if !face.dot(c, u, v) {
+                        continue;
+                    }
+                    let (lx, ly) = (left + u, offset.1 + v);
+                    let (px, py) = match rotate {
+                        Rotation::NoRotation => (origin.0 + lx, origin.1 + ly),
+                        Rotation::Rotation90 => (origin.0 - ly, origin.1 + lx),
+                        Rotation::Rotation180 => (origin.0 - lx, origin.1 - ly),
+                        Rotation::Rotation270 => (origin.0 + ly, origin.1 - lx),
+                    };
+                    self.update(px, py, |_| true);
+                }
+            }
+            left += advance;
+        }
+    }
+}
+
+/// Renders all elements of `label` at the given resolution.
+/// Text is drawn in approximations of the built-in fonts and barcodes are left out, see the [module documentation](self).
+pub fn render(label: &Label, dpi: u32) -> Result<Canvas> {
+    let mut canvas = Canvas::for_tape(&label.tape, dpi)?;
+    for element in &label.elements {
+        canvas.draw(element, dpi)?;
+    }
+    Ok(canvas)
+}
+
+/// Renders the first label of a TSPL job, up to its first `PRINT`, with the limits of [`render`].
+/// The canvas is sized by the `SIZE` command of the job, which has to precede the drawing commands.
+pub fn render_tspl(input: &[u8], dpi: u32) -> Result<Canvas> {
+    let missing_size = || Error::invalid("SIZE", "SIZE before drawing commands", "none");
+    let mut canvas = None;
+    for command in crate::parse(input)? {
+        match command {
+            Command::Size { width, height } => canvas = Some(Canvas::sized(width, height, dpi)?),
+            Command::Cls => canvas.as_mut().ok_or_else(missing_size)?.clear(),
+            Command::Draw(element) => canvas
+                .as_mut()
+                .ok_or_else(missing_size)?
+                .draw(&element, dpi)?,
+            Command::Print { .. } => break,
+            _ => {}
+        }
+    }
+    canvas.ok_or_else(missing_size)
+}
+
+/// Glyphs and metrics of a font at the size selected by the `TEXT` or `BLOCK` multipliers.
+enum Typeface {
+    /// Fixed pitch dot font, enlarged by whole multiples.
+    Bitmap {
+        font: font::BitmapFont,
+        scale: (i64, i64),
+    },
+    /// TrueType font sized in dots, with the advance widths of ROMAN.TTF and the glyphs of font 5.
+    Scalable { width: i64, height: i64 },
+}
+
+impl Typeface {
+    fn new(font: &Font, multiply_x: u8, multiply_y: u8, dpi: u32) -> Self {
+        let scale = (multiply_x.max(1) as i64, multiply_y.max(1) as i64);
+        let bitmap = |number| Self::Bitmap {
+            font: font::BitmapFont::get(number),
+            scale,
+        };
+        match font {
+            Font::Font8x12 => bitmap(1),
+            Font::Font12x20 => bitmap(2),
+            Font::Font16x24 => bitmap(3),
+            Font::Font24x32 => bitmap(4),
+            Font::Font32x48 => bitmap(5),
+            Font::Font14x19 => bitmap(6),
+            Font::Font21x27 => bitmap(7),
+            Font::Font14x25 => bitmap(8),
+            // Scalable fonts take the multipliers as point sizes.
+            font if font.is_scalable() => Self::Scalable {
+                width: (scale.0 * dpi as i64 / 72).max(1),
+                height: (scale.1 * dpi as i64 / 72).max(1),
+            },
+            _ => bitmap(2),
+        }
+    }
+
+    /// Line height in dots.
+    fn height(&self) -> i64 {
+        match self {
+            Self::Bitmap { font, scale } => font.height as i64 * scale.1,
+            Self::Scalable { height, .. } => *height,
+        }
+    }
+
+    /// Horizontal advance of `c` in dots.
+    fn advance(&self, c: char) -> i64 {
+        match self {
+            Self::Bitmap { font, scale } => font.width as i64 * scale.0,
+            Self::Scalable { width, .. } => (font::roman_width(c) as i64 * width / 1000).max(1),
+        }
+    }
+
+    fn width(&self, text: &str) -> i64 {
+        text.chars().map(|c| self.advance(c)).sum()
+    }
+
+    /// Returns true if the dot at `u`, `v` of the cell of `c` is printed.
+    fn dot(&self, c: char, u: i64, v: i64) -> bool {
+        match self {
+            Self::Bitmap { font, scale } => font.dot(c, (u / scale.0) as u32, (v / scale.1) as u32),
+            Self::Scalable { height, .. } => {
+                let font = font::BitmapFont::get(5);
+                let x = u * font.width as i64 / self.advance(c);
+                let y = v * font.height as i64 / height;
+                font.dot(c, x as u32, y as u32)
+            }
+        }
+    }
+}
+
+/// Greedy word wrap to lines at most `width` dots wide.
+fn wrap(content: &str, width: i64, face: &Typeface) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in content.split(' ') {
+        let joined = match line.is_empty() {
+            true => word.to_owned(),
+            false => format!("{line} {word}"),
+        };
+        if face.width(&joined) <= width {
+            line = joined;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // The word alone may be wider than the block, split it.
+        for c in word.chars() {
+            if !line.is_empty() && face.width(&line) + face.advance(c) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(c);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dots(canvas: &Canvas) -> Vec<String> {
+        (0..canvas.height())
+            .map(|y| {
+                (0..canvas.width())
+                    .map(|x| if canvas.get(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn geometry() {
+        let canvas = render_tspl(
+            b"SIZE 6 dot,4 dot\r\nCLS\r\nBAR 1,1,3,2\r\nREVERSE 2,0,2,4\r\nERASE 0,3,6,1\r\nPRINT 1\r\nBAR 0,0,6,4\r\n",
+            203,
+        )
+        .unwrap();
+        assert_eq!(dots(&canvas), ["..##..", ".#....", ".#....", "......"]);
+
+        let canvas = render_tspl(b"SIZE 4 dot\r\nCLS\r\nBAR 1,2,2,1\r\n", 203).unwrap();
+        assert_eq!(dots(&canvas), ["....", "....", ".##."]);
+    }
+
+    #[test]
+    fn oversized_canvas() {
+        assert!(matches!(
+            render_tspl(b"SIZE 400,400\r\nCLS\r\n", 600),
+            Err(Error::InvalidParameter { .. })
+        ));
+        assert!(Canvas::new(u32::MAX, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn growing_beyond_the_limit() {
+        let result = render_tspl(b"SIZE 4\r\nBAR 0,4000000000,1,1\r\n", 203);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidParameter {
+                field: "label height",
+                ..
+            })
+        ));
+        let result = render_tspl(b"SIZE 4\r\nBAR 0,0,4000000000,4000000000\r\n", 203);
+        assert!(result.is_err());
+
+        let mut canvas = Canvas::new(10, 10).unwrap();
+        let bar = Element::Bar {
+            x: Size::Dots(0),
+            y: Size::Dots(0),
+            width: Size::Dots(u32::MAX),
+            height: Size::Dots(u32::MAX),
+        };
+        canvas.draw(&bar, 203).unwrap();
+        assert!((0..10).all(|n| canvas.get(n, n)));
+    }
+
+    /// Bounding box of the printed dots, as left, top, right and bottom edges.
+    fn ink(canvas: &Canvas) -> (u32, u32, u32, u32) {
+        let printed: Vec<(u32, u32)> = (0..canvas.height())
+            .flat_map(|y| (0..canvas.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| canvas.get(x, y))
+            .collect();
+        let left = printed.iter().map(|p| p.0).min().unwrap();
+        let top = printed.iter().map(|p| p.1).min().unwrap();
+        let right = printed.iter().map(|p| p.0 + 1).max().unwrap();
+        let bottom = printed.iter().map(|p| p.1 + 1).max().unwrap();
+        (left, top, right, bottom)
+    }
+
+    fn text(font: Font, scale: u8, alignment: Option<Alignment>, content: &str) -> Canvas {
+        let mut canvas = Canvas::new(800, 400).unwrap();
+        let text = Element::Text {
+            x: Size::Dots(400),
+            y: Size::Dots(10),
+            font,
+            rotate: Rotation::NoRotation,
+            multiply_x: scale,
+            multiply_y: scale,
+            alignment,
+            content: content.into(),
+        };
+        canvas.draw(&text, 203).unwrap();
+        canvas
+    }
+
+    #[test]
+    fn packed_rows() {
+        let canvas = render_tspl(b"SIZE 10 dot,2 dot\r\nCLS\r\nBAR 1,1,8,1\r\n", 203).unwrap();
+        assert_eq!(canvas.row(0), [0, 0]);
+        assert_eq!(canvas.row(1), [0b0111_1111, 0b1000_0000]);
+
+        let mut png = Vec::new();
+        canvas.write_png(&mut png).unwrap();
+        let decoder = png::Decoder::new(std::io::Cursor::new(png));
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!(data, [0xff, 0xff, 0b1000_0000, 0b0111_1111]);
+    }
+
+    #[test]
+    fn bitmap_font_cells() {
+        let cells = [
+            (Font::Font8x12, 8, 12),
+            (Font::Font12x20, 12, 20),
+            (Font::Font16x24, 16, 24),
+            (Font::Font24x32, 24, 32),
+            (Font::Font32x48, 32, 48),
+            (Font::Font14x19, 14, 19),
+            (Font::Font21x27, 21, 27),
+            (Font::Font14x25, 14, 25),
+        ];
+        for (font, width, height) in cells {
+            for scale in [1, 2] {
+                let (width, height) = (width * scale, height * scale);
+                // The blank column and row of each cell separate the glyphs.
+                let (left, top, right, bottom) = ink(&text(font.clone(), scale as u8, None, "HH"));
+                assert!(left >= 400 && right <= 400 + 2 * width - scale, "{font}");
+                assert!(right - left > width, "{font}");
+                assert!(top >= 10 && bottom <= 10 + height - scale, "{font}");
+                assert!(bottom - top >= height / 2, "{font}");
+
+                let (left, _, right, _) = ink(&text(
+                    font.clone(),
+                    scale as u8,
+                    Some(Alignment::Right),
+                    "HH",
+                ));
+                assert!(left >= 400 - 2 * width && right <= 400, "{font}");
+                let (left, _, right, _) = ink(&text(
+                    font.clone(),
+                    scale as u8,
+                    Some(Alignment::Center),
+                    "HH",
+                ));
+                assert!(left >= 400 - width && right <= 400 + width, "{font}");
+            }
+        }
+    }
+
+    #[test]
+    fn roman_metrics() {
+        // 12 point at 203 dpi is 33 dots, W advances 0.993 and i 0.308 of that.
+        let face = Typeface::new(&Font::FontRoman, 12, 12, 203);
+        assert_eq!(face.height(), 33);
+        assert_eq!((face.advance('W'), face.advance('i')), (32, 10));
+        assert_eq!(face.width("Wi"), 42);
+        let custom = Typeface::new(&Font::Custom("ARIAL.TTF".into()), 12, 12, 203);
+        assert_eq!(custom.width("Wi"), 42);
+
+        let (left, top, right, bottom) =
+            ink(&text(Font::FontRoman, 12, Some(Alignment::Right), "Wi"));
+        assert!(left >= 400 - 42 && right <= 400);
+        assert!(top >= 10 && bottom <= 10 + 33);
+    }
+
+    #[test]
+    fn block_wraps_by_width() {
+        let face = Typeface::new(&Font::FontRoman, 12, 12, 203);
+        // "Wi Wi" is 42 + 10 + 42 dots wide.
+        assert_eq!(wrap("Wi Wi", 94, &face), ["Wi Wi"]);
+        assert_eq!(wrap("Wi Wi", 93, &face), ["Wi", "Wi"]);
+        assert_eq!(wrap("WWWW", 70, &face), ["WW", "WW"]);
+
+        let face = Typeface::new(&Font::Font8x12, 1, 1, 203);
+        assert_eq!(wrap("ab cd efghij", 32, &face), ["ab", "cd", "efgh", "ij"]);
+    }
+}
@@ -0,0 +1,103 @@
+/// Glyphs of printable ASCII for dot fonts 1 to 8, font after font, glyph after glyph.
+/// Rows run top to bottom, padded to whole bytes, the leftmost dot in the most significant bit
+/// and a set bit for a printed dot.
+///
+/// The firmware glyphs can't be redistributed, so these were rasterized from DejaVu Sans Mono
+/// (bold for fonts 1 to 5) at the cell size of each font, with a coverage threshold of 45%, or
+/// just below the darkest dot for thin glyphs that would vanish otherwise.
+/// Like the firmware fonts, the glyphs leave the last column and row of the cell blank.
+static GLYPHS: &[u8] = include_bytes!("glyphs.bin");
+
+/// Cell size of fonts 1 to 8 in dots, in the order of [`GLYPHS`].
+const CELLS: [(u32, u32); 8] = [
+    (8, 12),
+    (12, 20),
+    (16, 24),
+    (24, 32),
+    (32, 48),
+    (14, 19),
+    (21, 27),
+    (14, 25),
+];
+
+/// Advance widths of ROMAN.TTF for printable ASCII, in thousandths of the point size.
+/// Taken from DejaVu Sans Condensed Bold, which is close to the condensed bold design of the
+/// printer font.
+const ROMAN_WIDTHS: [u16; 95] = [
+    313, 410, 469, 754, 626, 901, 785, 275, 411, 411, 470, 754, 342, 374, 342, 329, 626, 626, 626,
+    626, 626, 626, 626, 626, 626, 626, 360, 360, 754, 754, 754, 522, 900, 696, 686, 660, 747, 615,
+    615, 738, 753, 334, 334, 697, 573, 896, 753, 765, 659, 765, 693, 648, 614, 730, 696, 993, 694,
+    651, 652, 411, 329, 411, 754, 450, 450, 607, 644, 533, 644, 610, 391, 644, 641, 308, 308, 598,
+    308, 938, 641, 618, 644, 644, 444, 536, 430, 641, 586, 831, 580, 586, 523, 641, 329, 641, 754,
+];
+
+/// Index of `c` in the glyph tables, characters outside printable ASCII show as `?`.
+fn index(c: char) -> usize {
+    match c {
+        ' '..='~' => c as usize - 0x20,
+        _ => '?' as usize - 0x20,
+    }
+}
+
+/// Glyphs of one of the fixed pitch dot fonts.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct BitmapFont {
+    pub(super) width: u32,
+    pub(super) height: u32,
+    glyphs: &'static [u8],
+}
+
+impl BitmapFont {
+    /// Dot font `number`, 1 to 8.
+    pub(super) fn get(number: usize) -> Self {
+        let size = |(width, height): (u32, u32)| 95 * height as usize * width.div_ceil(8) as usize;
+        let start: usize = CELLS[..number - 1].iter().copied().map(size).sum();
+        let (width, height) = CELLS[number - 1];
+        Self {
+            width,
+            height,
+            glyphs: &GLYPHS[start..start + size((width, height))],
+        }
+    }
+
+    /// Returns true if the dot at `x`, `y` of the glyph of `c` is printed.
+    pub(super) fn dot(&self, c: char, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let stride = self.width.div_ceil(8) as usize;
+        let row = (index(c) * self.height as usize + y as usize) * stride;
+        self.glyphs[row + x as usize / 8] & (0x80 >> (x % 8)) != 0
+    }
+}
+
+/// Advance width of `c` in ROMAN.TTF, in thousandths of the point size.
+pub(super) fn roman_width(c: char) -> u16 {
+    ROMAN_WIDTHS[index(c)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables() {
+        let last = BitmapFont::get(8);
+        assert_eq!(
+            last.glyphs.as_ptr_range().end,
+            GLYPHS.as_ptr_range().end,
+            "glyph table size"
+        );
+        for number in 1..=8 {
+            let font = BitmapFont::get(number);
+            let blank = |c| {
+                (0..font.width).all(|x| !font.dot(c, x, font.height - 1))
+                    && (0..font.height).all(|y| !font.dot(c, font.width - 1, y))
+            };
+            assert!(('!'..='~').all(blank), "spacing of font {number}");
+            let inked = |c| (0..font.height).any(|y| (0..font.width).any(|x| font.dot(c, x, y)));
+            assert!(('!'..='~').all(inked), "glyphs of font {number}");
+            assert!(!inked(' '));
+        }
+    }
+}
@@ -0,0 +1,557 @@
+use crate::{BitmapMode, Element, Error, Printer, Result, Rotation, Size};
+use std::io::Write;
+
+/// Conversion of gray levels to printed and blank dots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dithering {
+    /// Pixels darker than the level are printed.
+    Threshold(u8),
+    /// Floyd–Steinberg error diffusion, best for photos.
+    #[default]
+    FloydSteinberg,
+    /// 4x4 Bayer matrix, keeps flat areas regular and compresses well.
+    Ordered,
+}
+
+/// 8 bit grayscale image, row by row, 0 being black and 255 white.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrayImage {
+    width: u32,
+    height: u32,
+    pub(crate) pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    /// Wraps one byte per pixel.
+    pub fn from_luma(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self> {
+        let expected = width as usize * height as usize;
+        if pixels.len() != expected {
+            return Err(Error::invalid(
+                "pixels",
+                format!("{expected} bytes"),
+                pixels.len(),
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Converts four bytes per pixel RGBA data. Transparent pixels are blended with white paper.
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8]) -> Result<Self> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(Error::invalid(
+                "pixels",
+                format!("{expected} bytes"),
+                pixels.len(),
+            ));
+        }
+        let pixels = pixels
+            .chunks_exact(4)
+            .map(|p| {
+                let luma = (299 * p[0] as u32 + 587 * p[1] as u32 + 114 * p[2] as u32) / 1000;
+                let alpha = p[3] as u32;
+                ((luma * alpha + 255 * (255 - alpha)) / 255) as u8
+            })
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Loads a PNG, JPEG or BMP file.
+    #[cfg(feature = "image")]
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let image = image::open(path).map_err(|e| match e {
+            image::ImageError::IoError(e) => Error::Io(e),
+            e => Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        })?;
+        let rgba = image.to_rgba8();
+        Self::from_rgba(rgba.width(), rgba.height(), rgba.as_raw())
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Scales the image to `width` x `height` pixels, averaging the source pixels covered by each target pixel.
+    pub fn resize(&self, width: u32, height: u32) -> Self {
+        if (width, height) == (self.width, self.height) {
+            return self.clone();
+        }
+        if self.pixels.is_empty() {
+            return Self {
+                width,
+                height,
+                pixels: vec![255; width as usize * height as usize],
+            };
+        }
+
+        let span = |n: u32, target: u32, source: u32| {
+            let start = n as u64 * source as u64 / target as u64;
+            let end = ((n as u64 + 1) * source as u64).div_ceil(target as u64);
+            start as usize..(end as usize).max(start as usize + 1)
+        };
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            let rows = span(y, height, self.height);
+            for x in 0..width {
+                let cols = span(x, width, self.width);
+                let (mut sum, mut count) = (0u64, 0u64);
+                for row in rows.clone() {
+                    let row = &self.pixels[row * self.width as usize..][..self.width as usize];
+                    sum += row[cols.clone()].iter().map(|&p| p as u64).sum::<u64>();
+                    count += cols.len() as u64;
+                }
+                pixels.push((sum / count) as u8);
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Rotates the image clockwise.
+    pub fn rotate(&self, rotation: Rotation) -> Self {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let (width, height) = match rotation {
+            Rotation::NoRotation | Rotation::Rotation180 => (w, h),
+            Rotation::Rotation90 | Rotation::Rotation270 => (h, w),
+        };
+        let mut pixels = Vec::with_capacity(w * h);
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = match rotation {
+                    Rotation::NoRotation => (x, y),
+                    Rotation::Rotation90 => (y, h - 1 - x),
+                    Rotation::Rotation180 => (w - 1 - x, h - 1 - y),
+                    Rotation::Rotation270 => (w - 1 - y, x),
+                };
+                pixels.push(self.pixels[sy * w + sx]);
+            }
+        }
+        Self {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        }
+    }
+
+    /// Converts the image to TSPL bitmap data.
+    pub fn to_bitmap(&self, dithering: Dithering) -> Result<Bitmap> {
+        let width_bytes = self.width.div_ceil(8);
+        if width_bytes > u16::MAX as u32 {
+            return Err(Error::invalid("width", "up to 524280 dots", self.width));
+        }
+        if self.height > u16::MAX as u32 {
+            return Err(Error::invalid("height", "up to 65535 dots", self.height));
+        }
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let row_bytes = width_bytes as usize;
+        // Padding and blank dots are 1, TSPL prints the 0 bits.
+        let mut data = vec![0xff; row_bytes * height];
+        let mut print = |x: usize, y: usize| data[y * row_bytes + x / 8] &= !(0x80 >> (x % 8));
+
+        match dithering {
+            Dithering::Threshold(level) => {
+                for (n, &p) in self.pixels.iter().enumerate() {
+                    if p < level {
+                        print(n % width, n / width);
+                    }
+                }
+            }
+            Dithering::Ordered => {
+                const BAYER: [[u8; 4]; 4] =
+                    [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+                for (n, &p) in self.pixels.iter().enumerate() {
+                    let (x, y) = (n % width, n / width);
+                    if p < BAYER[y % 4][x % 4] * 16 + 8 {
+                        print(x, y);
+                    }
+                }
+            }
+            Dithering::FloydSteinberg => {
+                let mut levels: Vec<i16> = self.pixels.iter().map(|&p| p as i16).collect();
+                for y in 0..height {
+                    for x in 0..width {
+                        let old = levels[y * width + x];
+                        let new = if old < 128 { 0 } else { 255 };
+                        if new == 0 {
+                            print(x, y);
+                        }
+                        let error = old - new;
+                        let mut spread = |dx: isize, dy: usize, weight: i16| {
+                            let nx = x as isize + dx;
+                            if nx >= 0 && (nx as usize) < width && y + dy < height {
+                                levels[(y + dy) * width + nx as usize] += error * weight / 16;
+                            }
+                        };
+                        spread(1, 0, 7);
+                        spread(-1, 1, 3);
+                        spread(0, 1, 5);
+                        spread(1, 1, 1);
+                    }
+                }
+            }
+        }
+
+        Ok(Bitmap {
+            width_bytes: width_bytes as u16,
+            height_dots: self.height as u16,
+            data,
+        })
+    }
+
+    /// Encodes the image as a monochrome BMP file for [`Printer::download`] and [`Printer::put_bmp`].
+    pub fn to_bmp(&self, dithering: Dithering) -> Result<Vec<u8>> {
+        let bitmap = self.to_bitmap(dithering)?;
+        let row_bytes = bitmap.width_bytes as usize;
+        // BMP rows are padded to 4 bytes.
+        let stride = row_bytes.div_ceil(4) * 4;
+        let offset = 14 + 40 + 8;
+        let size = offset + stride * self.height as usize;
+
+        let mut bmp = Vec::with_capacity(size);
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&(size as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&(offset as u32).to_le_bytes());
+
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&(self.width as i32).to_le_bytes());
+        bmp.extend_from_slice(&(self.height as i32).to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&((stride * self.height as usize) as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 8]);
+        bmp.extend_from_slice(&2u32.to_le_bytes());
+        bmp.extend_from_slice(&2u32.to_le_bytes());
+        // Palette: index 0 black, index 1 white, matching the bitmap bits.
+        bmp.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 0]);
+
+        // Rows are stored bottom-up.
+        for row in bitmap.data.chunks_exact(row_bytes.max(1)).rev() {
+            bmp.extend_from_slice(row);
+            bmp.resize(bmp.len() + stride - row_bytes, 0xff);
+        }
+        Ok(bmp)
+    }
+
+    /// Encodes the image as a monochrome, run-length encoded PCX file for [`Printer::download`] and
+    /// [`Printer::put_pcx`].
+    pub fn to_pcx(&self, dithering: Dithering) -> Result<Vec<u8>> {
+        let bitmap = self.to_bitmap(dithering)?;
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::invalid(
+                "size",
+                "at least 1x1 pixels",
+                format!("{}x{}", self.width, self.height),
+            ));
+        }
+        let row_bytes = bitmap.width_bytes as usize;
+        // PCX lines hold an even number of bytes.
+        let line_bytes = row_bytes.div_ceil(2) * 2;
+
+        let mut pcx = Vec::with_capacity(128 + bitmap.data.len());
+        pcx.extend_from_slice(&[0x0a, 5, 1, 1]);
+        pcx.extend_from_slice(&0u16.to_le_bytes());
+        pcx.extend_from_slice(&0u16.to_le_bytes());
+        pcx.extend_from_slice(&((self.width - 1) as u16).to_le_bytes());
+        pcx.extend_from_slice(&((self.height - 1) as u16).to_le_bytes());
+        pcx.extend_from_slice(&[0; 4]);
+        // 16 color header palette: index 0 black, index 1 white.
+        pcx.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+        pcx.resize(pcx.len() + 42, 0);
+        pcx.push(0);
+        pcx.push(1);
+        pcx.extend_from_slice(&(line_bytes as u16).to_le_bytes());
+        pcx.extend_from_slice(&1u16.to_le_bytes());
+        pcx.resize(128, 0);
+
+        let mut line = Vec::with_capacity(line_bytes);
+        for row in bitmap.data.chunks_exact(row_bytes) {
+            line.clear();
+            line.extend_from_slice(row);
+            line.resize(line_bytes, 0xff);
+
+            let mut bytes = line.iter().peekable();
+            while let Some(&byte) = bytes.next() {
+                let mut count = 1;
+                while count < 63 && bytes.next_if_eq(&&byte).is_some() {
+                    count += 1;
+                }
+                if count > 1 || byte & 0xc0 == 0xc0 {
+                    pcx.push(0xc0 | count);
+                }
+                pcx.push(byte);
+            }
+        }
+        Ok(pcx)
+    }
+}
+
+/// 1 bit image packed the way `BITMAP` expects it: rows of `width_bytes` bytes, most significant bit first,
+/// 0 for a printed dot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    pub width_bytes: u16,
+    pub height_dots: u16,
+    pub data: Vec<u8>,
+}
+
+impl Bitmap {
+    /// Creates the label element drawing the bitmap at `x`, `y`.
+    pub fn into_element(self, x: Size, y: Size, mode: BitmapMode) -> Element {
+        Element::Bitmap {
+            x,
+            y,
+            width_bytes: self.width_bytes,
+            height_dots: self.height_dots,
+            mode,
+            data: self.data,
+        }
+    }
+}
+
+impl<T: Write> Printer<T> {
+    /// Draws a grayscale image scaled to `width` and `height` at the printer resolution.
+    /// If `height` is `None`, it follows from the aspect ratio of the image.
+    #[allow(clippy::too_many_arguments)]
+    pub fn image(
+        &mut self,
+        x: Size,
+        y: Size,
+        image: &GrayImage,
+        width: Size,
+        height: Option<Size>,
+        dithering: Dithering,
+        mode: BitmapMode,
+    ) -> Result<&mut Self> {
+        let width_dots = width.to_dots_raw(self.resolution);
+        let height_dots = match height {
+            Some(height) => height.to_dots_raw(self.resolution) as u64,
+            None if image.width == 0 => 0,
+            None => image.height as u64 * width_dots as u64 / image.width as u64,
+        };
+        // Check the BITMAP limits before scaling, an oversized target would be allocated first.
+        if width_dots.div_ceil(8) > u16::MAX as u32 {
+            return Err(Error::invalid("width", "up to 524280 dots", width_dots));
+        }
+        if height_dots > u16::MAX as u64 {
+            return Err(Error::invalid("height", "up to 65535 dots", height_dots));
+        }
+        let height_dots = height_dots as u32;
+        let bitmap = image.resize(width_dots, height_dots).to_bitmap(dithering)?;
+        self.bitmap(
+            x,
+            y,
+            bitmap.width_bytes,
+            bitmap.height_dots,
+            mode,
+            bitmap.data,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(width: u32, height: u32, pixels: &[u8]) -> GrayImage {
+        GrayImage::from_luma(width, height, pixels.to_vec()).unwrap()
+    }
+
+    /// 16 pixels from black to nearly white.
+    fn gradient() -> GrayImage {
+        gray(16, 1, &(0..16).map(|x| x * 16).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn packing() {
+        // Printed dots are 0 bits, most significant bit first, padding is 1.
+        let rows = [
+            [0, 255, 255, 0, 0, 0, 0, 0, 255, 0],
+            [255, 255, 0, 0, 0, 0, 0, 0, 0, 255],
+        ];
+        let image = gray(10, 2, rows.as_flattened());
+        let bitmap = image.to_bitmap(Dithering::Threshold(128)).unwrap();
+        assert_eq!((bitmap.width_bytes, bitmap.height_dots), (2, 2));
+        assert_eq!(
+            bitmap.data,
+            [0b0110_0000, 0b1011_1111, 0b1100_0000, 0b0111_1111]
+        );
+
+        let blank = gray(3, 1, &[255; 3]).to_bitmap(Dithering::Ordered).unwrap();
+        assert_eq!(blank.data, [0xff]);
+    }
+
+    #[test]
+    fn dithering() {
+        let row = |dithering| gradient().to_bitmap(dithering).unwrap().data;
+        assert_eq!(row(Dithering::Threshold(128)), [0b0000_0000, 0b1111_1111]);
+        assert_eq!(row(Dithering::Threshold(0)), [0xff, 0xff]);
+        assert_eq!(row(Dithering::Ordered), [0b0000_1010, 0b1111_1111]);
+        assert_eq!(row(Dithering::FloydSteinberg), [0b0000_0010, 0b1011_1111]);
+
+        // Flat gray prints a share of the dots matching its darkness.
+        let printed = |level, dithering| {
+            let bitmap = gray(16, 16, &[level; 256]).to_bitmap(dithering).unwrap();
+            bitmap.data.iter().map(|b| b.count_zeros()).sum::<u32>()
+        };
+        for dithering in [Dithering::Ordered, Dithering::FloydSteinberg] {
+            assert_eq!(printed(0, dithering), 256);
+            assert_eq!(printed(128, dithering), 128);
+            assert!((180..=200).contains(&printed(64, dithering)));
+            assert!((56..=72).contains(&printed(192, dithering)));
+            assert_eq!(printed(255, dithering), 0);
+        }
+    }
+
+    #[test]
+    fn resize() {
+        let image = gray(4, 2, &[0, 100, 200, 255, 50, 50, 50, 50]);
+        assert_eq!(image.resize(2, 1), gray(2, 1, &[50, 138]));
+        assert_eq!(
+            gray(3, 1, &[0, 90, 255]).resize(2, 1),
+            gray(2, 1, &[45, 172])
+        );
+        assert_eq!(gray(1, 1, &[7]).resize(2, 2), gray(2, 2, &[7; 4]));
+        assert_eq!(gray(0, 0, &[]).resize(2, 1), gray(2, 1, &[255; 2]));
+    }
+
+    #[test]
+    fn rotate() {
+        let image = gray(3, 2, &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(image.rotate(Rotation::NoRotation), image);
+        assert_eq!(
+            image.rotate(Rotation::Rotation90),
+            gray(2, 3, &[4, 1, 5, 2, 6, 3])
+        );
+        assert_eq!(
+            image.rotate(Rotation::Rotation180),
+            gray(3, 2, &[6, 5, 4, 3, 2, 1])
+        );
+        assert_eq!(
+            image.rotate(Rotation::Rotation270),
+            gray(2, 3, &[3, 6, 2, 5, 1, 4])
+        );
+    }
+
+    #[test]
+    fn bmp() {
+        // Rows are black, white and a black dot at x = 9.
+        let mut pixels = vec![0; 10];
+        pixels.extend([255; 10]);
+        pixels.extend([255, 255, 255, 255, 255, 255, 255, 255, 255, 0]);
+        let bmp = gray(10, 3, &pixels)
+            .to_bmp(Dithering::Threshold(128))
+            .unwrap();
+
+        let u32_at = |n: usize| u32::from_le_bytes(bmp[n..n + 4].try_into().unwrap());
+        let u16_at = |n: usize| u16::from_le_bytes(bmp[n..n + 2].try_into().unwrap());
+        assert_eq!(&bmp[..2], b"BM");
+        // 2 bytes per row are padded to 4.
+        assert_eq!((bmp.len(), u32_at(2) as usize), (62 + 3 * 4, 62 + 3 * 4));
+        assert_eq!(u32_at(10), 62);
+        assert_eq!((u32_at(14), u32_at(18), u32_at(22)), (40, 10, 3));
+        assert_eq!((u16_at(26), u16_at(28), u32_at(30)), (1, 1, 0));
+        assert_eq!((u32_at(34), u32_at(46), u32_at(50)), (12, 2, 2));
+        assert_eq!(&bmp[54..62], [0, 0, 0, 0, 255, 255, 255, 0]);
+        // Bottom row first.
+        assert_eq!(
+            &bmp[62..],
+            [
+                [0xff, 0b1011_1111, 0xff, 0xff],
+                [0xff, 0xff, 0xff, 0xff],
+                [0x00, 0b0011_1111, 0xff, 0xff],
+            ]
+            .as_flattened()
+        );
+    }
+
+    #[test]
+    fn pcx() {
+        // Rows pack to 0xC3 and 0x0F, padded to 2 bytes with 0xFF.
+        let rows = [
+            [255, 255, 0, 0, 0, 0, 255, 255],
+            [0, 0, 0, 0, 255, 255, 255, 255],
+        ];
+        let pcx = gray(8, 2, rows.as_flattened())
+            .to_pcx(Dithering::Threshold(128))
+            .unwrap();
+        assert_eq!(pcx[..4], [0x0a, 5, 1, 1]);
+        assert_eq!(pcx[4..12], [0, 0, 0, 0, 7, 0, 1, 0]);
+        assert_eq!(pcx[16..22], [0, 0, 0, 255, 255, 255]);
+        assert_eq!(pcx[64..70], [0, 1, 2, 0, 1, 0]);
+        assert!(pcx[70..128].iter().all(|&b| b == 0));
+        // Bytes of 0xC0 and above need a run count even when single.
+        assert_eq!(pcx[128..], [0xc1, 0xc3, 0xc1, 0xff, 0x0f, 0xc1, 0xff]);
+
+        // Runs are split at 63 bytes.
+        let pcx = gray(130 * 8, 1, &[255; 130 * 8])
+            .to_pcx(Dithering::Threshold(128))
+            .unwrap();
+        assert_eq!(pcx[128..], [0xff, 0xff, 0xff, 0xff, 0xc4, 0xff]);
+        let pcx = gray(8 * 64, 1, &[0; 8 * 64])
+            .to_pcx(Dithering::Threshold(128))
+            .unwrap();
+        assert_eq!(pcx[128..], [0xff, 0x00, 0x00]);
+
+        assert!(gray(0, 0, &[]).to_pcx(Dithering::Threshold(128)).is_err());
+    }
+
+    #[test]
+    fn image_limits() {
+        let mut printer = Printer::unconfigured(Vec::new(), 203);
+        let image = gray(1, 1, &[0]);
+        let mut draw = |width, height: Option<u32>| {
+            printer
+                .image(
+                    Size::Dots(0),
+                    Size::Dots(0),
+                    &image,
+                    Size::Dots(width),
+                    height.map(Size::Dots),
+                    Dithering::Threshold(128),
+                    BitmapMode::Overwrite,
+                )
+                .map(drop)
+        };
+        // Rejected before the scaled image would be allocated.
+        assert!(matches!(
+            draw(u32::MAX, Some(1)),
+            Err(Error::InvalidParameter { field: "width", .. })
+        ));
+        assert!(matches!(
+            draw(1, Some(u32::MAX)),
+            Err(Error::InvalidParameter {
+                field: "height",
+                ..
+            })
+        ));
+        // The height following from the aspect ratio is checked as well.
+        assert!(matches!(
+            draw(65536, None),
+            Err(Error::InvalidParameter {
+                field: "height",
+                ..
+            })
+        ));
+        draw(524_280, Some(1)).unwrap();
+        draw(16, None).unwrap();
+    }
+}
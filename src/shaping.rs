@@ -0,0 +1,673 @@
+use crate::{Alignment, BitmapMode, Dithering, Error, GrayImage, Printer, Result, Rotation, Size};
+use ab_glyph_rasterizer::{point, Point, Rasterizer};
+use rustybuzz::{
+    ttf_parser::{GlyphId, RasterImageFormat},
+    Direction, Face, UnicodeBuffer,
+};
+use std::{io::Write, ops::Range};
+use unicode_bidi::{BidiInfo, ParagraphInfo};
+
+/// TrueType or OpenType font used to render text on the host, for scripts the printer can't render itself.
+/// Text is laid out with bidirectional reordering and OpenType shaping, drawn at the printer resolution
+/// and sent as a bitmap.
+#[derive(Debug, Clone)]
+pub struct OutlineFont {
+    data: Vec<u8>,
+    index: u32,
+}
+
+/// Glyph positioned on a line, in dots from the line start and the baseline.
+struct Glyph {
+    id: GlyphId,
+    x: f32,
+    y: f32,
+}
+
+struct Line {
+    glyphs: Vec<Glyph>,
+    width: f32,
+    rtl: bool,
+}
+
+impl OutlineFont {
+    /// Uses the first font of a TTF, OTF or TTC file.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        Self::from_collection(data, 0)
+    }
+
+    /// Uses the font at `index` of a font collection.
+    pub fn from_collection(data: Vec<u8>, index: u32) -> Result<Self> {
+        if Face::from_slice(&data, index).is_none() {
+            return Err(Error::invalid(
+                "font data",
+                "TrueType or OpenType font",
+                format!("{} bytes", data.len()),
+            ));
+        }
+        Ok(Self { data, index })
+    }
+
+    /// Loads the first font of a TTF, OTF or TTC file.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    fn face(&self) -> Face<'_> {
+        // Checked when the font was created.
+        Face::from_slice(&self.data, self.index).expect("valid font")
+    }
+
+    /// Renders a single line of text `points` (1/72 inch) high at `dpi`.
+    pub fn render_line(&self, content: &str, points: f32, dpi: u32) -> Result<GrayImage> {
+        let face = self.face();
+        let scale = scale(&face, points, dpi)?;
+        let bidi = BidiInfo::new(content, None);
+        let lines: Vec<Line> = bidi
+            .paragraphs
+            .iter()
+            .map(|para| layout(&face, &bidi, para, trim(content, para.range.clone()), scale))
+            .collect();
+        let width = lines
+            .iter()
+            .map(|line| line.width)
+            .fold(0.0, f32::max)
+            .ceil() as u32;
+        draw(&face, &lines, scale, width, None, 0, Alignment::Left)
+    }
+
+    /// Renders text wrapped at word boundaries to `width` dots. Lines are `space` dots apart,
+    /// lines below `height` dots are left out.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_block(
+        &self,
+        content: &str,
+        points: f32,
+        dpi: u32,
+        width: u32,
+        height: u32,
+        space: u32,
+        alignment: Alignment,
+    ) -> Result<GrayImage> {
+        let face = self.face();
+        let scale = scale(&face, points, dpi)?;
+        let bidi = BidiInfo::new(content, None);
+        let mut lines = Vec::new();
+        for para in &bidi.paragraphs {
+            let range = trim(content, para.range.clone());
+            let (mut start, mut end) = (range.start, range.start);
+            let word_ends = content[range.clone()]
+                .match_indices(' ')
+                .map(|(n, _)| range.start + n)
+                .chain([range.end]);
+            for word_end in word_ends {
+                if end > start && advance(&face, &content[start..word_end]) * scale > width as f32 {
+                    lines.push(layout(&face, &bidi, para, start..end, scale));
+                    // The line break replaces the space.
+                    start = end + 1;
+                }
+                end = word_end;
+            }
+            lines.push(layout(&face, &bidi, para, start..end, scale));
+        }
+        draw(&face, &lines, scale, width, Some(height), space, alignment)
+    }
+}
+
+impl<T: Write> Printer<T> {
+    /// Draws text rendered on the host from `font`, with the same placement as [`Printer::text`]:
+    /// `x` is the left edge, center or right edge depending on `alignment`, and the text is rotated
+    /// clockwise around `x`, `y`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn shaped_text(
+        &mut self,
+        x: Size,
+        y: Size,
+        font: &OutlineFont,
+        rotate: Rotation,
+        points: f32,
+        alignment: Option<Alignment>,
+        content: &str,
+    ) -> Result<&mut Self> {
+        let image = font.render_line(content, points, self.resolution)?;
+        let offset = match alignment {
+            Some(Alignment::Center) => image.width() as i64 / 2,
+            Some(Alignment::Right) => image.width() as i64,
+            _ => 0,
+        };
+        self.shaped_bitmap(x, y, offset, rotate, &image)
+    }
+
+    /// Draws text rendered on the host from `font` and wrapped into a block, like [`Printer::block`].
+    /// [`Alignment::Default`] aligns right-to-left paragraphs to the right.
+    #[allow(clippy::too_many_arguments)]
+    pub fn shaped_block(
+        &mut self,
+        x: Size,
+        y: Size,
+        width: Size,
+        height: Size,
+        font: &OutlineFont,
+        rotate: Rotation,
+        points: f32,
+        space: Option<Size>,
+        alignment: Option<Alignment>,
+        content: &str,
+    ) -> Result<&mut Self> {
+        let image = font.render_block(
+            content,
+            points,
+            self.resolution,
+            width.to_dots_raw(self.resolution),
+            height.to_dots_raw(self.resolution),
+            space.map_or(0, |space| space.to_dots_raw(self.resolution)),
+            alignment.unwrap_or(Alignment::Default),
+        )?;
+        self.shaped_bitmap(x, y, 0, rotate, &image)
+    }
+
+    /// Sends rendered text with its left edge `offset` dots before `x`, rotated around `x`, `y`.
+    fn shaped_bitmap(
+        &mut self,
+        x: Size,
+        y: Size,
+        offset: i64,
+        rotate: Rotation,
+        image: &GrayImage,
+    ) -> Result<&mut Self> {
+        if image.width() == 0 || image.height() == 0 {
+            return Ok(self);
+        }
+        let (x, y) = (
+            x.to_dots_raw(self.resolution) as i64,
+            y.to_dots_raw(self.resolution) as i64,
+        );
+        let (w, h) = (image.width() as i64, image.height() as i64);
+        let (left, top) = match rotate {
+            Rotation::NoRotation => (x - offset, y),
+            Rotation::Rotation90 => (x - h, y - offset),
+            Rotation::Rotation180 => (x + offset - w, y - h),
+            Rotation::Rotation270 => (x, y + offset - w),
+        };
+        if left < 0 || top < 0 {
+            return Err(Error::invalid(
+                "position",
+                "text inside the label",
+                format!("{left},{top} dots"),
+            ));
+        }
+
+        let bitmap = image.rotate(rotate).to_bitmap(Dithering::Threshold(128))?;
+        self.bitmap(
+            Size::Dots(left as u32),
+            Size::Dots(top as u32),
+            bitmap.width_bytes,
+            bitmap.height_dots,
+            BitmapMode::Or,
+            bitmap.data,
+        )
+    }
+}
+
+/// Dots per font unit.
+fn scale(face: &Face, points: f32, dpi: u32) -> Result<f32> {
+    if !(points.is_finite() && points > 0.0) {
+        return Err(Error::invalid("points", "positive size", points));
+    }
+    Ok(points * dpi as f32 / 72.0 / face.units_per_em() as f32)
+}
+
+/// `range` without the trailing paragraph separator.
+fn trim(content: &str, range: Range<usize>) -> Range<usize> {
+    let len = content[range.clone()]
+        .trim_end_matches(['\r', '\n', '\u{2029}'])
+        .len();
+    range.start..range.start + len
+}
+
+/// Width of `text` in font units.
+fn advance(face: &Face, text: &str) -> f32 {
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.guess_segment_properties();
+    let shaped = rustybuzz::shape(face, &[], buffer);
+    shaped
+        .glyph_positions()
+        .iter()
+        .map(|p| p.x_advance as f32)
+        .sum()
+}
+
+/// Shapes the `range` of a paragraph as one line, its runs in visual order.
+fn layout(
+    face: &Face,
+    bidi: &BidiInfo,
+    para: &ParagraphInfo,
+    range: Range<usize>,
+    scale: f32,
+) -> Line {
+    let mut glyphs = Vec::new();
+    let mut pen = 0.0;
+    if !range.is_empty() {
+        let (levels, runs) = bidi.visual_runs(para, range);
+        for run in runs {
+            let mut buffer = UnicodeBuffer::new();
+            buffer.push_str(&bidi.text[run.clone()]);
+            buffer.set_direction(match levels[run.start].is_rtl() {
+                true => Direction::RightToLeft,
+                false => Direction::LeftToRight,
+            });
+            buffer.guess_segment_properties();
+            let shaped = rustybuzz::shape(face, &[], buffer);
+            for (info, position) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+                glyphs.push(Glyph {
+                    id: GlyphId(info.glyph_id as u16),
+                    x: pen + position.x_offset as f32 * scale,
+                    y: position.y_offset as f32 * scale,
+                });
+                pen += position.x_advance as f32 * scale;
+            }
+        }
+    }
+    Line {
+        glyphs,
+        width: pen,
+        rtl: para.level.is_rtl(),
+    }
+}
+
+/// Draws `lines` on a white image `width` dots wide, cut off at `height` if given.
+fn draw(
+    face: &Face,
+    lines: &[Line],
+    scale: f32,
+    width: u32,
+    height: Option<u32>,
+    space: u32,
+    alignment: Alignment,
+) -> Result<GrayImage> {
+    let ascent = face.ascender() as f32 * scale;
+    let line_height = (face.ascender() - face.descender()) as f32 * scale;
+    let pitch = line_height + face.line_gap() as f32 * scale + space as f32;
+
+    let fitting = match height {
+        Some(height) => lines
+            .iter()
+            .enumerate()
+            .take_while(|(n, _)| *n as f32 * pitch + line_height <= height as f32)
+            .count(),
+        None => lines.len(),
+    };
+    let image_height = match fitting {
+        0 => 0,
+        n => ((n - 1) as f32 * pitch + line_height).ceil() as u32,
+    };
+
+    let (w, h) = (width as usize, image_height as usize);
+    let mut coverage = vec![0.0f32; w * h];
+    for (n, line) in lines[..fitting].iter().enumerate() {
+        let left = match alignment {
+            Alignment::Center => (width as f32 - line.width) / 2.0,
+            Alignment::Right => width as f32 - line.width,
+            Alignment::Default if line.rtl => width as f32 - line.width,
+            _ => 0.0,
+        };
+        let baseline = n as f32 * pitch + ascent;
+        for glyph in &line.glyphs {
+            let origin = point(left + glyph.x, baseline - glyph.y);
+            draw_glyph(face, glyph.id, scale, origin, &mut coverage, w, h)?;
+        }
+    }
+
+    let pixels = coverage
+        .iter()
+        .map(|c| 255 - (c.min(1.0) * 255.0) as u8)
+        .collect();
+    GrayImage::from_luma(width, image_height, pixels)
+}
+
+/// Adds the coverage of a glyph with its origin at `origin` to `coverage`, clipped to `width` x `height`.
+fn draw_glyph(
+    face: &Face,
+    id: GlyphId,
+    scale: f32,
+    origin: Point,
+    coverage: &mut [f32],
+    width: usize,
+    height: usize,
+) -> Result<()> {
+    let Some(rect) = face.glyph_bounding_box(id) else {
+        // Color emoji fonts store images instead of outlines, glyphs with neither are blank.
+        return draw_image(face, id, scale, origin, coverage, width, height);
+    };
+    let left = (origin.x + rect.x_min as f32 * scale).floor();
+    let top = (origin.y - rect.y_max as f32 * scale).floor();
+    let right = (origin.x + rect.x_max as f32 * scale).ceil();
+    let bottom = (origin.y - rect.y_min as f32 * scale).ceil();
+
+    let mut outline = Outline {
+        rasterizer: Rasterizer::new((right - left) as usize + 1, (bottom - top) as usize + 1),
+        scale,
+        origin: point(origin.x - left, origin.y - top),
+        start: point(0.0, 0.0),
+        last: point(0.0, 0.0),
+    };
+    face.outline_glyph(id, &mut outline);
+    outline.rasterizer.for_each_pixel_2d(|gx, gy, alpha| {
+        let (x, y) = (left as i64 + gx as i64, top as i64 + gy as i64);
+        if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+            let c = &mut coverage[y as usize * width + x as usize];
+            *c = c.max(alpha);
+        }
+    });
+    Ok(())
+}
+
+/// Adds the darkness of the `sbix` or `CBDT` image of a glyph, scaled from the closest strike.
+fn draw_image(
+    face: &Face,
+    id: GlyphId,
+    scale: f32,
+    origin: Point,
+    coverage: &mut [f32],
+    width: usize,
+    height: usize,
+) -> Result<()> {
+    let pixels_per_em = scale * face.units_per_em() as f32;
+    let Some(raster) = face.glyph_raster_image(id, pixels_per_em.round() as u16) else {
+        return Ok(());
+    };
+    let unsupported = || {
+        Error::invalid(
+            "glyph",
+            "outline or PNG image",
+            format!("{:?} image of glyph {}", raster.format, id.0),
+        )
+    };
+    if raster.format != RasterImageFormat::PNG {
+        return Err(unsupported());
+    }
+    let image = decode_png(raster.data).ok_or_else(unsupported)?;
+
+    // Offsets are in pixels of the strike, from the origin to the bottom left corner.
+    let zoom = pixels_per_em / raster.pixels_per_em.max(1) as f32;
+    let left = origin.x + raster.x as f32 * zoom;
+    let top = origin.y - (raster.y as f32 + image.height() as f32) * zoom;
+    let right = (left + image.width() as f32 * zoom).ceil() as i64;
+    let bottom = (top + image.height() as f32 * zoom).ceil() as i64;
+    for y in (top.floor() as i64).max(0)..bottom.min(height as i64) {
+        for x in (left.floor() as i64).max(0)..right.min(width as i64) {
+            // Nearest pixel of the image.
+            let sx = ((x as f32 + 0.5 - left) / zoom) as i64;
+            let sy = ((y as f32 + 0.5 - top) / zoom) as i64;
+            if (0..image.width() as i64).contains(&sx) && (0..image.height() as i64).contains(&sy) {
+                let gray = image.pixels[sy as usize * image.width() as usize + sx as usize];
+                let c = &mut coverage[y as usize * width + x as usize];
+                *c = c.max(1.0 - gray as f32 / 255.0);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a PNG glyph image, blended with white paper.
+fn decode_png(data: &[u8]) -> Option<GrayImage> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).ok()?;
+    let rgba: Vec<u8> = match info.color_type {
+        png::ColorType::Grayscale => buf[..info.buffer_size()]
+            .iter()
+            .flat_map(|&l| [l, l, l, 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buf[..info.buffer_size()]
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Rgb => buf[..info.buffer_size()]
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::Rgba => buf[..info.buffer_size()].to_vec(),
+        png::ColorType::Indexed => return None,
+    };
+    GrayImage::from_rgba(info.width, info.height, &rgba).ok()
+}
+
+/// Feeds glyph outlines in font units to the rasterizer.
+struct Outline {
+    rasterizer: Rasterizer,
+    scale: f32,
+    origin: Point,
+    start: Point,
+    last: Point,
+}
+
+impl Outline {
+    fn map(&self, x: f32, y: f32) -> Point {
+        point(
+            self.origin.x + x * self.scale,
+            self.origin.y - y * self.scale,
+        )
+    }
+}
+
+impl rustybuzz::ttf_parser::OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.map(x, y);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.map(x, y);
+        self.rasterizer.draw_line(self.last, p);
+        self.last = p;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let p = self.map(x, y);
+        self.rasterizer.draw_quad(self.last, self.map(x1, y1), p);
+        self.last = p;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let p = self.map(x, y);
+        self.rasterizer
+            .draw_cubic(self.last, self.map(x1, y1), self.map(x2, y2), p);
+        self.last = p;
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.rasterizer.draw_line(self.last, self.start);
+        }
+        self.last = self.start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test font with 1000 units per em, 800 ascender and 200 descender. `A`, `B`, `א` and `ב`
+    /// each fill a band a fifth of the em high, from the top down, between 10% and 90% of their
+    /// advance. U+1F600 is a PNG image with a black left half, U+1F601 a monochrome image.
+    fn font() -> OutlineFont {
+        OutlineFont::from_bytes(include_bytes!("shaping/bands.ttf").to_vec()).unwrap()
+    }
+
+    /// Rendered at 20 points and 72 dpi, an em is 20 dots and the baseline 16 dots down.
+    fn line(content: &str) -> GrayImage {
+        font().render_line(content, 20.0, 72).unwrap()
+    }
+
+    /// Horizontal extent of the dark pixels of `row`.
+    fn ink(image: &GrayImage, row: u32) -> Option<Range<u32>> {
+        let dark: Vec<u32> = (0..image.width())
+            .filter(|&x| image.pixels[(row * image.width() + x) as usize] < 128)
+            .collect();
+        Some(*dark.first()?..dark.last()? + 1)
+    }
+
+    /// Middle rows of the bands of `A`, `B`, `א` and `ב`.
+    const A: u32 = 2;
+    const B: u32 = 6;
+    const ALEF: u32 = 10;
+    const BET: u32 = 14;
+
+    #[test]
+    fn visual_order() {
+        let image = line("AB אב");
+        assert_eq!((image.width(), image.height()), (90, 20));
+        assert_eq!(ink(&image, A), Some(2..18));
+        assert_eq!(ink(&image, B), Some(22..38));
+        // The Hebrew run reads right to left.
+        assert_eq!(ink(&image, BET), Some(52..68));
+        assert_eq!(ink(&image, ALEF), Some(72..88));
+
+        // A right-to-left paragraph starts at the right.
+        let image = line("אב A");
+        assert_eq!(image.width(), 70);
+        assert_eq!(ink(&image, A), Some(2..18));
+        assert_eq!(ink(&image, BET), Some(32..48));
+        assert_eq!(ink(&image, ALEF), Some(52..68));
+
+        assert!(font().render_line("A", 0.0, 72).is_err());
+        assert!(font().render_line("A", f32::NAN, 72).is_err());
+        assert!(OutlineFont::from_bytes(b"true".to_vec()).is_err());
+    }
+
+    #[test]
+    fn block() {
+        let block = |content: &str, width, height, space, alignment| {
+            font()
+                .render_block(content, 20.0, 72, width, height, space, alignment)
+                .unwrap()
+        };
+        // Wrapped at spaces into lines 20 dots high and 25 dots apart.
+        let image = block("AA AA AA", 50, 100, 5, Alignment::Left);
+        assert_eq!((image.width(), image.height()), (50, 70));
+        for top in [0, 25, 50] {
+            assert_eq!(ink(&image, top + A), Some(2..38));
+        }
+        assert_eq!(ink(&image, 22), None);
+
+        // Lines not fitting the height entirely are left out.
+        let image = block("AA AA AA", 50, 69, 5, Alignment::Left);
+        assert_eq!(image.height(), 45);
+        let image = block("AA AA AA", 50, 19, 5, Alignment::Left);
+        assert_eq!(image.height(), 0);
+
+        // A word wider than the block stays on its line.
+        let image = block("AAA A", 50, 100, 0, Alignment::Left);
+        assert_eq!(image.height(), 40);
+        assert_eq!(ink(&image, A), Some(2..50));
+        assert_eq!(ink(&image, 20 + A), Some(2..18));
+
+        let image = block("AA B", 50, 100, 0, Alignment::Right);
+        assert_eq!(ink(&image, A), Some(12..48));
+        assert_eq!(ink(&image, 20 + B), Some(32..48));
+        let image = block("AA", 50, 100, 0, Alignment::Center);
+        assert_eq!(ink(&image, A), Some(7..43));
+        // Right-to-left paragraphs are aligned right by default.
+        let image = block("אב\nAB", 50, 100, 0, Alignment::Default);
+        assert_eq!(ink(&image, BET), Some(12..28));
+        assert_eq!(ink(&image, 20 + A), Some(2..18));
+    }
+
+    #[test]
+    fn bitmap_glyphs() {
+        // The 10 ppem PNG is scaled to the 20 dot em, 8 pixels above and 2 below the baseline.
+        let image = line("A\u{1F600}");
+        assert_eq!(image.width(), 40);
+        for row in 0..20 {
+            assert_eq!(ink(&image, row).map(|ink| ink.end), Some(30), "row {row}");
+        }
+        assert_eq!(ink(&image, B), Some(20..30));
+
+        assert!(matches!(
+            font().render_line("\u{1F601}", 20.0, 72),
+            Err(Error::InvalidParameter { field: "glyph", .. })
+        ));
+    }
+
+    /// Position and size of the `BITMAP` drawn by `shaped_text`.
+    fn placement(x: u32, y: u32, rotate: Rotation, alignment: Option<Alignment>) -> Result<String> {
+        let mut printer = Printer::unconfigured(Vec::new(), 72);
+        printer.shaped_text(
+            Size::Dots(x),
+            Size::Dots(y),
+            &font(),
+            rotate,
+            20.0,
+            alignment,
+            "AB",
+        )?;
+        let cmd = printer.take_bytes();
+        let header: Vec<&[u8]> = cmd.splitn(6, |&b| b == b',').take(4).collect();
+        Ok(String::from_utf8(header.join(&b","[..])).unwrap())
+    }
+
+    #[test]
+    fn rotation_and_alignment() {
+        use Rotation::*;
+        // "AB" is 40 dots wide and 20 dots high, BITMAP takes the width in bytes.
+        let cases = [
+            (NoRotation, None, "BITMAP 100,50,5,20"),
+            (NoRotation, Some(Alignment::Center), "BITMAP 80,50,5,20"),
+            (NoRotation, Some(Alignment::Right), "BITMAP 60,50,5,20"),
+            (Rotation90, None, "BITMAP 80,50,3,40"),
+            (Rotation90, Some(Alignment::Right), "BITMAP 80,10,3,40"),
+            (Rotation180, None, "BITMAP 60,30,5,20"),
+            (Rotation180, Some(Alignment::Right), "BITMAP 100,30,5,20"),
+            (Rotation270, None, "BITMAP 100,10,3,40"),
+            (Rotation270, Some(Alignment::Right), "BITMAP 100,50,3,40"),
+        ];
+        for (rotate, alignment, expected) in cases {
+            assert_eq!(
+                placement(100, 50, rotate, alignment).unwrap(),
+                expected,
+                "{rotate:?} {alignment:?}"
+            );
+        }
+
+        // Text reaching past the top or left edge of the label.
+        for (x, y, rotate, alignment) in [
+            (10, 50, NoRotation, Some(Alignment::Right)),
+            (10, 50, Rotation90, None),
+            (100, 10, Rotation180, None),
+            (100, 30, Rotation270, None),
+        ] {
+            assert!(
+                matches!(
+                    placement(x, y, rotate, alignment),
+                    Err(Error::InvalidParameter {
+                        field: "position",
+                        ..
+                    })
+                ),
+                "{rotate:?} at {x},{y}"
+            );
+        }
+
+        // The rotated dots follow the rotation of the bitmap, "A" starts at the top.
+        let mut printer = Printer::unconfigured(Vec::new(), 72);
+        printer
+            .shaped_text(
+                Size::Dots(40),
+                Size::Dots(0),
+                &font(),
+                Rotation90,
+                20.0,
+                None,
+                "A",
+            )
+            .unwrap();
+        let cmd = printer.take_bytes();
+        let data = &cmd["BITMAP 20,0,3,20,1,".len()..cmd.len() - 2];
+        // Row 5 crosses the A band, 16 to 20 dots from the left after rotation.
+        assert_eq!(data[5 * 3..6 * 3], [0xff, 0xff, 0b0000_1111]);
+        assert_eq!(data[..3], [0xff, 0xff, 0xff]);
+    }
+}
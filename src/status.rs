@@ -0,0 +1,317 @@
+use crate::{Error, Printer, Result};
+use log::debug;
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    time::{Duration, Instant},
+};
+
+/// Printer status returned by `<ESC>!?`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Status {
+    pub head_open: bool,
+    pub paper_jam: bool,
+    pub paper_empty: bool,
+    pub ribbon_empty: bool,
+    pub paused: bool,
+    pub printing: bool,
+    pub cover_open: bool,
+    /// Environment temperature is out of the operating range.
+    pub temperature_out_of_range: bool,
+}
+
+impl Status {
+    /// Decodes the status byte.
+    pub fn from_byte(byte: u8) -> Self {
+        Self {
+            head_open: byte & 0x01 != 0,
+            paper_jam: byte & 0x02 != 0,
+            paper_empty: byte & 0x04 != 0,
+            ribbon_empty: byte & 0x08 != 0,
+            paused: byte & 0x10 != 0,
+            printing: byte & 0x20 != 0,
+            cover_open: byte & 0x40 != 0,
+            temperature_out_of_range: byte & 0x80 != 0,
+        }
+    }
+
+    /// Returns true if nothing prevents the printer from accepting a new job.
+    pub fn is_ready(&self) -> bool {
+        !(self.head_open
+            || self.paper_jam
+            || self.paper_empty
+            || self.ribbon_empty
+            || self.paused
+            || self.cover_open
+            || self.temperature_out_of_range)
+    }
+}
+
+/// Operating state reported in the first byte of `<ESC>!S` response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PrinterState {
+    Normal,
+    HeadOpen,
+    Pause,
+    BackingLabel,
+    Cutting,
+    Error,
+    FormFeed,
+    WaitingForPrintKey,
+    WaitingForLabelTaken,
+    PrintingBatch,
+    Imaging,
+    Unknown(u8),
+}
+
+impl From<u8> for PrinterState {
+    fn from(value: u8) -> Self {
+        match value {
+            b'@' => Self::Normal,
+            b'A' => Self::HeadOpen,
+            b'E' => Self::Pause,
+            b'B' => Self::BackingLabel,
+            b'C' => Self::Cutting,
+            b'%' => Self::Error,
+            b'F' => Self::FormFeed,
+            b'K' => Self::WaitingForPrintKey,
+            b'L' => Self::WaitingForLabelTaken,
+            b'P' => Self::PrintingBatch,
+            b'W' => Self::Imaging,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+/// Detailed printer status returned by `<ESC>!S`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedStatus {
+    pub state: PrinterState,
+    pub paper_low: bool,
+    pub ribbon_low: bool,
+    pub head_overheat: bool,
+    pub motor_overheat: bool,
+    pub head_error: bool,
+    pub cutter_jam: bool,
+    pub out_of_memory: bool,
+    pub paper_empty: bool,
+    pub paper_jam: bool,
+    pub ribbon_empty: bool,
+    pub ribbon_jam: bool,
+    pub ribbon_encoder_error: bool,
+}
+
+impl ExtendedStatus {
+    /// Decodes the 8 byte response: `<STX>`, state, warning, error and media error bytes, `<ETX><CR><LF>`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let [0x02, state, warning, error, media, 0x03, b'\r', b'\n'] = bytes else {
+            return Err(Error::InvalidResponse(format!(
+                "extended status {bytes:02X?}"
+            )));
+        };
+
+        Ok(Self {
+            state: PrinterState::from(*state),
+            paper_low: warning & 0x01 != 0,
+            ribbon_low: warning & 0x02 != 0,
+            head_overheat: error & 0x01 != 0,
+            motor_overheat: error & 0x02 != 0,
+            head_error: error & 0x04 != 0,
+            cutter_jam: error & 0x08 != 0,
+            out_of_memory: error & 0x10 != 0,
+            paper_empty: media & 0x01 != 0,
+            paper_jam: media & 0x02 != 0,
+            ribbon_empty: media & 0x04 != 0,
+            ribbon_jam: media & 0x08 != 0,
+            ribbon_encoder_error: media & 0x10 != 0,
+        })
+    }
+
+    pub fn head_open(&self) -> bool {
+        self.state == PrinterState::HeadOpen
+    }
+
+    pub fn paused(&self) -> bool {
+        self.state == PrinterState::Pause
+    }
+
+    pub fn printing(&self) -> bool {
+        self.state == PrinterState::PrintingBatch
+    }
+}
+
+impl<T: Read + Write> Printer<T> {
+    /// Queries the printer status with `<ESC>!?`.
+    /// The command is executed immediately, even if the printer is busy or in error state.
+    /// See [`Printer::set_timeout`] for transports which block while the printer doesn't answer.
+    pub fn status(&mut self) -> Result<Status> {
+        let response = self.query(b"\x1b!?", |r| !r.is_empty())?;
+        Ok(Status::from_byte(response[0]))
+    }
+
+    /// Queries the printer status and fails with [`Error::PrinterFault`] if the printer can't print.
+    pub fn ensure_ready(&mut self) -> Result<Status> {
+        let status = self.status()?;
+        if status.is_ready() {
+            Ok(status)
+        } else {
+            Err(Error::PrinterFault(status))
+        }
+    }
+
+    /// Queries the detailed printer status with `<ESC>!S`.
+    pub fn extended_status(&mut self) -> Result<ExtendedStatus> {
+        let response = self.query(b"\x1b!S", |r| r.len() == 8 || r.ends_with(b"\r\n"))?;
+        ExtendedStatus::from_bytes(&response)
+    }
+
+    /// Sends `cmd` and reads the response until `complete` returns true or the timeout expires.
+    /// The transport returning no data at all means the connection was closed.
+    pub(crate) fn query(
+        &mut self,
+        cmd: &[u8],
+        complete: impl Fn(&[u8]) -> bool,
+    ) -> Result<Vec<u8>> {
+        debug!("{}", String::from_utf8_lossy(cmd));
+        self.transport.write_all(cmd)?;
+        self.transport.flush()?;
+
+        let deadline = Instant::now() + self.timeout;
+        let mut response = Vec::new();
+        let mut byte = [0u8];
+        while !complete(&response) {
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout);
+            }
+            match self.transport.read(&mut byte) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "connection closed while waiting for the response",
+                    )
+                    .into())
+                }
+                Ok(_) => response.push(byte[0]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(10))
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        debug!("response: {response:02X?}");
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Transport answering with canned bytes, then timing out like a socket with a read timeout.
+    pub(crate) struct Mock {
+        pub(crate) written: Vec<u8>,
+        reply: VecDeque<u8>,
+        closed: bool,
+    }
+
+    impl Mock {
+        pub(crate) fn printer(reply: &[u8], closed: bool) -> Printer<Self> {
+            let mut printer = Printer::unconfigured(
+                Self {
+                    written: Vec::new(),
+                    reply: reply.iter().copied().collect(),
+                    closed,
+                },
+                203,
+            );
+            printer.set_timeout(Duration::from_millis(50));
+            printer
+        }
+    }
+
+    impl Read for Mock {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.reply.pop_front() {
+                Some(byte) => {
+                    buf[0] = byte;
+                    Ok(1)
+                }
+                None if self.closed => Ok(0),
+                None => {
+                    std::thread::sleep(Duration::from_millis(5));
+                    Err(ErrorKind::TimedOut.into())
+                }
+            }
+        }
+    }
+
+    impl Write for Mock {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn status() {
+        let mut printer = Mock::printer(&[0x25], false);
+        let status = printer.status().unwrap();
+        assert_eq!(printer.get_ref().written, b"\x1b!?");
+        assert!(status.head_open && status.paper_empty && status.printing);
+        assert!(!(status.paper_jam || status.paused || status.cover_open));
+        assert!(!status.is_ready());
+
+        let mut printer = Mock::printer(&[0x10], false);
+        assert!(matches!(
+            printer.ensure_ready(),
+            Err(Error::PrinterFault(Status { paused: true, .. }))
+        ));
+        let mut printer = Mock::printer(&[0x20], false);
+        assert!(printer.ensure_ready().unwrap().printing);
+    }
+
+    #[test]
+    fn extended_status() {
+        let mut printer = Mock::printer(b"\x02E\x01\x04\x09\x03\r\n", false);
+        let status = printer.extended_status().unwrap();
+        assert_eq!(printer.get_ref().written, b"\x1b!S");
+        assert_eq!(status.state, PrinterState::Pause);
+        assert!(status.paused() && status.paper_low && status.head_error);
+        assert!(status.paper_empty && status.ribbon_jam);
+        assert!(!(status.ribbon_low || status.cutter_jam || status.paper_jam));
+
+        let mut printer = Mock::printer(b"garbage\r\n", false);
+        assert!(matches!(
+            printer.extended_status(),
+            Err(Error::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn no_reply() {
+        let mut printer = Mock::printer(&[], false);
+        assert!(matches!(printer.status(), Err(Error::Timeout)));
+        let mut printer = Mock::printer(b"\x02@", false);
+        assert!(matches!(printer.extended_status(), Err(Error::Timeout)));
+    }
+
+    #[test]
+    fn closed_connection() {
+        let mut printer = Mock::printer(b"\x02@", true);
+        let start = Instant::now();
+        match printer.extended_status() {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), ErrorKind::UnexpectedEof),
+            other => panic!("unexpected result {other:?}"),
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+}
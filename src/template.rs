@@ -0,0 +1,356 @@
+use crate::{Element, Error, Label, Result};
+use std::collections::HashMap;
+
+/// Transformation applied to a variable before it is substituted into a placeholder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Formatter {
+    /// Pad the value with leading zeros up to the given width.
+    ZeroPad(usize),
+    Uppercase,
+    Lowercase,
+    /// Reformat an ISO 8601 date (`YYYY-MM-DD`). The pattern may contain `%Y`, `%y`, `%m`, `%d` and `%%`.
+    Date(String),
+}
+
+impl Formatter {
+    /// Applies the formatter to `value` of variable `name`.
+    pub fn apply(&self, name: &str, value: &str) -> Result<String> {
+        match self {
+            Self::ZeroPad(width) => Ok(format!("{value:0>width$}")),
+            Self::Uppercase => Ok(value.to_uppercase()),
+            Self::Lowercase => Ok(value.to_lowercase()),
+            Self::Date(pattern) => format_date(pattern, value).ok_or_else(|| {
+                Error::Template(format!("{name}: {value:?} is not a YYYY-MM-DD date"))
+            }),
+        }
+    }
+}
+
+fn format_date(pattern: &str, value: &str) -> Option<String> {
+    let mut parts = value.split('-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    let valid = |s: &str, len| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    if parts.next().is_some() || !(valid(year, 4) && valid(month, 2) && valid(day, 2)) {
+        return None;
+    }
+    let leap = |y: u32| y.is_multiple_of(4) && (!y.is_multiple_of(100) || y.is_multiple_of(400));
+    let days = match month.parse::<u32>().ok()? {
+        2 if leap(year.parse().ok()?) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if !(1..=days).contains(&day.parse::<u32>().ok()?) {
+        return None;
+    }
+
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'Y' => out.push_str(year),
+            'y' => out.push_str(&year[2..]),
+            'm' => out.push_str(month),
+            'd' => out.push_str(day),
+            '%' => out.push('%'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Label with `{name}` placeholders in its content strings.
+/// `{{` and `}}` stand for literal braces.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Template {
+    pub label: Label,
+    /// Formatters applied, in order, to the variable of the same name.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub formatters: HashMap<String, Vec<Formatter>>,
+}
+
+impl Template {
+    pub fn new(label: Label) -> Self {
+        Self {
+            label,
+            formatters: HashMap::new(),
+        }
+    }
+
+    /// Adds a formatter for variable `name`.
+    pub fn format(&mut self, name: &str, formatter: Formatter) -> &mut Self {
+        self.formatters
+            .entry(name.to_owned())
+            .or_default()
+            .push(formatter);
+        self
+    }
+
+    /// Names of all placeholders used by the template, in order of first appearance.
+    pub fn placeholders(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for content in contents(&self.label.elements) {
+            for name in parse(content)?.into_iter().filter_map(|part| match part {
+                Part::Placeholder(name) => Some(name),
+                Part::Literal(_) => None,
+            }) {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Substitutes the placeholders with `vars`, returning a label ready to print.
+    /// Fails with [`Error::MissingVariables`] listing every placeholder without a value.
+    pub fn render(&self, vars: &HashMap<&str, String>) -> Result<Label> {
+        let mut label = self.label.clone();
+        let mut missing = Vec::new();
+
+        for content in contents_mut(&mut label.elements) {
+            let mut rendered = String::with_capacity(content.len());
+            for part in parse(content)? {
+                match part {
+                    Part::Literal(s) => rendered.push_str(s),
+                    Part::Placeholder(name) => match vars.get(name) {
+                        Some(value) => rendered.push_str(&self.apply(name, value)?),
+                        None => {
+                            if !missing.iter().any(|n| n == name) {
+                                missing.push(name.to_owned());
+                            }
+                        }
+                    },
+                }
+            }
+            *content = rendered;
+        }
+
+        if missing.is_empty() {
+            Ok(label)
+        } else {
+            Err(Error::MissingVariables(missing))
+        }
+    }
+
+    fn apply(&self, name: &str, value: &str) -> Result<String> {
+        let mut value = value.to_owned();
+        for formatter in self.formatters.get(name).into_iter().flatten() {
+            value = formatter.apply(name, &value)?;
+        }
+        Ok(value)
+    }
+}
+
+enum Part<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn parse(content: &str) -> Result<Vec<Part<'_>>> {
+    let mut parts = Vec::new();
+    let mut rest = content;
+    while let Some(pos) = rest.find(['{', '}']) {
+        if pos > 0 {
+            parts.push(Part::Literal(&rest[..pos]));
+        }
+        let tail = &rest[pos..];
+        if let Some(tail) = tail.strip_prefix("{{") {
+            parts.push(Part::Literal("{"));
+            rest = tail;
+        } else if let Some(tail) = tail.strip_prefix("}}") {
+            parts.push(Part::Literal("}"));
+            rest = tail;
+        } else if tail.starts_with('{') {
+            let end = tail
+                .find('}')
+                .ok_or_else(|| Error::Template(format!("unclosed placeholder in {content:?}")))?;
+            let name = tail[1..end].trim();
+            if name.is_empty() || name.contains('{') {
+                return Err(Error::Template(format!(
+                    "malformed placeholder in {content:?}"
+                )));
+            }
+            parts.push(Part::Placeholder(name));
+            rest = &tail[end + 1..];
+        } else {
+            return Err(Error::Template(format!("unmatched '}}' in {content:?}")));
+        }
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest));
+    }
+    Ok(parts)
+}
+
+fn contents(elements: &[Element]) -> impl Iterator<Item = &String> {
+    elements.iter().flat_map(|element| match element {
+        Element::Text { content, .. }
+        | Element::Block { content, .. }
+        | Element::Barcode { content, .. }
+        | Element::QrCode { content, .. }
+        | Element::DataMatrix { content, .. }
+        | Element::Pdf417 { content, .. }
+        | Element::MicroPdf417 { content, .. }
+        | Element::Aztec { content, .. }
+        | Element::Codablock { content, .. }
+        | Element::Rss { content, .. } => vec![content],
+        Element::Tlc39 {
+            eci_number,
+            serial_number,
+            additional_data,
+            ..
+        } => vec![eci_number, serial_number, additional_data],
+        _ => vec![],
+    })
+}
+
+fn contents_mut(elements: &mut [Element]) -> impl Iterator<Item = &mut String> {
+    elements.iter_mut().flat_map(|element| match element {
+        Element::Text { content, .. }
+        | Element::Block { content, .. }
+        | Element::Barcode { content, .. }
+        | Element::QrCode { content, .. }
+        | Element::DataMatrix { content, .. }
+        | Element::Pdf417 { content, .. }
+        | Element::MicroPdf417 { content, .. }
+        | Element::Aztec { content, .. }
+        | Element::Codablock { content, .. }
+        | Element::Rss { content, .. } => vec![content],
+        Element::Tlc39 {
+            eci_number,
+            serial_number,
+            additional_data,
+            ..
+        } => vec![eci_number, serial_number, additional_data],
+        _ => vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Font, Rotation, Size, Tape};
+
+    fn text(content: &str) -> Element {
+        Element::Text {
+            x: Size::Dots(0),
+            y: Size::Dots(0),
+            font: Font::Font8x12,
+            rotate: Rotation::NoRotation,
+            multiply_x: 1,
+            multiply_y: 1,
+            alignment: None,
+            content: content.to_owned(),
+        }
+    }
+
+    fn template(contents: &[&str]) -> Template {
+        let mut label = Label::new(Tape {
+            width: Size::Metric(40.0),
+            height: Some(Size::Metric(30.0)),
+            gap: Size::Metric(2.0),
+            gap_offset: None,
+        });
+        for content in contents {
+            label.push(text(content));
+        }
+        Template::new(label)
+    }
+
+    fn contents(label: &Label) -> Vec<&str> {
+        super::contents(&label.elements)
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let template = template(&["SKU {sku}", "{ lot }/{sku}", "none"]);
+        assert_eq!(template.placeholders().unwrap(), ["sku", "lot"]);
+        let vars = HashMap::from([("sku", "A-1042".to_owned()), ("lot", "7".to_owned())]);
+        let label = template.render(&vars).unwrap();
+        assert_eq!(contents(&label), ["SKU A-1042", "7/A-1042", "none"]);
+    }
+
+    #[test]
+    fn escaped_braces() {
+        let template = template(&["{{{name}}} {{}}"]);
+        let vars = HashMap::from([("name", "x".to_owned())]);
+        assert_eq!(contents(&template.render(&vars).unwrap()), ["{x} {}"]);
+
+        for content in ["{name", "name}", "{}", "{a{b}"] {
+            let result = self::template(&[content]).render(&vars);
+            assert!(matches!(result, Err(Error::Template(_))), "{content}");
+        }
+    }
+
+    #[test]
+    fn missing_variables() {
+        let template = template(&["{a} {b}", "{c} {a}", "{d}"]);
+        let vars = HashMap::from([("d", "4".to_owned())]);
+        match template.render(&vars) {
+            Err(Error::MissingVariables(names)) => assert_eq!(names, ["a", "b", "c"]),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn formatters() {
+        let mut template = template(&["{lot} {name} {code} {date}"]);
+        template
+            .format("lot", Formatter::ZeroPad(6))
+            .format("name", Formatter::Uppercase)
+            .format("code", Formatter::Lowercase)
+            .format("code", Formatter::ZeroPad(4))
+            .format("date", Formatter::Date("%d.%m.%y (%Y) 100%%".to_owned()));
+        let vars = HashMap::from([
+            ("lot", "42".to_owned()),
+            ("name", "Straße".to_owned()),
+            ("code", "AB".to_owned()),
+            ("date", "2024-02-29".to_owned()),
+        ]);
+        assert_eq!(
+            contents(&template.render(&vars).unwrap()),
+            ["000042 STRASSE 00ab 29.02.24 (2024) 100%"]
+        );
+        assert_eq!(Formatter::ZeroPad(2).apply("n", "12345").unwrap(), "12345");
+    }
+
+    #[test]
+    fn dates() {
+        let date = Formatter::Date("%Y%m%d".to_owned());
+        for valid in ["2024-01-31", "2000-02-29", "2023-12-01", "1999-04-30"] {
+            assert_eq!(date.apply("date", valid).unwrap(), valid.replace('-', ""));
+        }
+        for invalid in [
+            "2024-13-45",
+            "2024-00-10",
+            "2024-04-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-01-00",
+            "2024-01-01T10:00",
+            "2024-01-01 ",
+            "2024-1-01",
+            "24-01-01",
+            "2024-01-01-",
+            "2024年01-01",
+            "",
+        ] {
+            assert!(
+                matches!(date.apply("date", invalid), Err(Error::Template(_))),
+                "{invalid}"
+            );
+        }
+        let bad_pattern = Formatter::Date("%Q".to_owned());
+        assert!(bad_pattern.apply("date", "2024-01-01").is_err());
+    }
+}
@@ -0,0 +1,9 @@
+//! Transports the printer can be attached to, besides plain [`std::fs::File`] device nodes.
+
+mod net;
+#[cfg(feature = "serial")]
+mod serial;
+
+pub use net::{NetworkConfig, NetworkTransport};
+#[cfg(feature = "serial")]
+pub use serial::{FlowControl, SerialConfig, SerialTransport};
@@ -0,0 +1,428 @@
+use crate::TryClone;
+use log::{debug, warn};
+use socket2::{SockRef, TcpKeepalive};
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// Settings of the raw TCP ("JetDirect") connection to a network printer.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkConfig {
+    /// Port used when the address does not contain one. Default is 9100.
+    pub port: u16,
+    /// Maximum time to wait for the connection to be established. `None` blocks until the OS gives up.
+    pub connect_timeout: Option<Duration>,
+    /// Maximum time a single write may block. `None` blocks indefinitely.
+    pub write_timeout: Option<Duration>,
+    /// Maximum time a single read may block. `None` blocks indefinitely.
+    pub read_timeout: Option<Duration>,
+    /// Idle time before TCP keepalive probes are sent. `None` disables keepalive.
+    pub keepalive: Option<Duration>,
+    /// How many times to reconnect and resend the current label when the connection drops.
+    pub reconnect_attempts: u32,
+    /// Pause between reconnect attempts.
+    pub reconnect_delay: Duration,
+    /// Largest label kept for resending, in bytes. A longer label is not resent after a reconnect.
+    pub resend_limit: usize,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            port: 9100,
+            connect_timeout: Some(Duration::from_secs(5)),
+            write_timeout: Some(Duration::from_secs(10)),
+            read_timeout: Some(Duration::from_secs(5)),
+            keepalive: Some(Duration::from_secs(30)),
+            reconnect_attempts: 3,
+            reconnect_delay: Duration::from_secs(1),
+            resend_limit: 1 << 20,
+        }
+    }
+}
+
+/// Raw TCP connection to a network printer.
+///
+/// The transport keeps a copy of every command written since the last `CLS`.
+/// If the connection drops in the middle of a label, it reconnects and resends
+/// the whole label, so the printer never receives a truncated job.
+/// The copy is released once the `PRINT` command of the label has been written.
+/// Real-time commands and queries (`<ESC>!`, `~!`) are never resent.
+/// A single write may carry several labels, only the one being written is resent.
+pub struct NetworkTransport {
+    addrs: Vec<SocketAddr>,
+    config: NetworkConfig,
+    stream: Option<TcpStream>,
+    label: Vec<u8>,
+    /// The current label outgrew `resend_limit` and can't be resent.
+    overflow: bool,
+}
+
+impl NetworkTransport {
+    /// Connects to `addr`, which is either `host:port` or just `host`,
+    /// in which case the port from `config` is used.
+    pub fn connect(addr: &str, config: NetworkConfig) -> io::Result<Self> {
+        let addrs: Vec<SocketAddr> = match addr.to_socket_addrs() {
+            Ok(addrs) => addrs.collect(),
+            Err(_) => (addr, config.port).to_socket_addrs()?.collect(),
+        };
+
+        let stream = Self::open(&addrs, &config)?;
+        Ok(Self {
+            addrs,
+            config,
+            stream: Some(stream),
+            label: Vec::new(),
+            overflow: false,
+        })
+    }
+
+    /// Drops the current connection and establishes a new one.
+    pub fn reconnect(&mut self) -> io::Result<()> {
+        self.stream = None;
+        self.stream = Some(Self::open(&self.addrs, &self.config)?);
+        Ok(())
+    }
+
+    /// Address of the connected printer.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream()?.peer_addr()
+    }
+
+    /// Connection settings of the transport.
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    fn open(addrs: &[SocketAddr], config: &NetworkConfig) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in addrs {
+            let stream = match config.connect_timeout {
+                Some(timeout) => TcpStream::connect_timeout(addr, timeout),
+                None => TcpStream::connect(addr),
+            };
+            match stream {
+                Ok(stream) => {
+                    stream.set_write_timeout(config.write_timeout)?;
+                    stream.set_read_timeout(config.read_timeout)?;
+                    stream.set_nodelay(true)?;
+                    if let Some(time) = config.keepalive {
+                        SockRef::from(&stream)
+                            .set_tcp_keepalive(&TcpKeepalive::new().with_time(time))?;
+                    }
+                    debug!("connected to {addr}");
+                    return Ok(stream);
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to")
+        }))
+    }
+
+    fn stream(&self) -> io::Result<&TcpStream> {
+        self.stream
+            .as_ref()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))
+    }
+
+    fn is_disconnect(e: &io::Error) -> bool {
+        matches!(
+            e.kind(),
+            io::ErrorKind::BrokenPipe
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::NotConnected
+                | io::ErrorKind::UnexpectedEof
+        )
+    }
+
+    /// Keeps a copy of label commands for [`Self::resend`].
+    fn record(&mut self, buf: &[u8]) {
+        for command in commands(buf) {
+            if command.starts_with(b"\x1b") || command.starts_with(b"~!") {
+                continue;
+            }
+            if command.starts_with(b"CLS") {
+                self.label.clear();
+                self.overflow = false;
+            }
+            if self.overflow {
+                continue;
+            }
+            if self.label.len() + command.len() > self.config.resend_limit {
+                warn!(
+                    "label exceeds {} bytes, it won't be resent on reconnect",
+                    self.config.resend_limit
+                );
+                self.label = Vec::new();
+                self.overflow = true;
+                continue;
+            }
+            self.label.extend_from_slice(command);
+        }
+    }
+
+    /// Writes `buf`, which holds at most one `PRINT` command at its end,
+    /// resending the current label if the connection drops.
+    fn send(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.record(buf);
+
+        let result = match self.stream.as_mut() {
+            Some(stream) => stream.write_all(buf),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        };
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) if Self::is_disconnect(&e) => self.resend(e),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reconnects and sends the current label again from its beginning.
+    fn resend(&mut self, cause: io::Error) -> io::Result<()> {
+        if self.overflow {
+            self.stream = None;
+            return Err(cause);
+        }
+        let mut last_err = cause;
+        for attempt in 1..=self.config.reconnect_attempts {
+            warn!(
+                "connection lost ({last_err}), resending label, attempt {attempt}/{}",
+                self.config.reconnect_attempts
+            );
+            std::thread::sleep(self.config.reconnect_delay);
+
+            let result = self.reconnect().and_then(|_| {
+                let stream = self.stream.as_mut().expect("just reconnected");
+                stream.write_all(&self.label)
+            });
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) => last_err = e,
+            }
+        }
+
+        self.stream = None;
+        Err(last_err)
+    }
+}
+
+impl TryClone for NetworkTransport {
+    /// Duplicates the current connection. The duplicate does not reconnect on its own.
+    fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            addrs: self.addrs.clone(),
+            config: NetworkConfig {
+                reconnect_attempts: 0,
+                ..self.config.clone()
+            },
+            stream: Some(self.stream()?.try_clone()?),
+            label: Vec::new(),
+            overflow: false,
+        })
+    }
+}
+
+impl Write for NetworkTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Each label is written on its own and released after its `PRINT` command,
+        // so a disconnect doesn't resend labels the printer already got.
+        let mut rest = buf;
+        while !rest.is_empty() {
+            let mut len = 0;
+            let mut print = false;
+            for command in commands(rest) {
+                len += command.len();
+                if command.starts_with(b"PRINT") {
+                    print = true;
+                    break;
+                }
+            }
+            let (label, tail) = rest.split_at(len);
+            self.send(label)?;
+            if print {
+                self.label.clear();
+                self.overflow = false;
+            }
+            rest = tail;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.stream.as_mut() {
+            Some(stream) => stream.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Read for NetworkTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.stream.as_mut() {
+            Some(stream) => stream.read(buf),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+}
+
+/// Splits `buf` into commands: real-time commands, which are 3 bytes without line end, and lines.
+fn commands(mut buf: &[u8]) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        if buf.is_empty() {
+            return None;
+        }
+        let len = if buf.starts_with(b"\x1b") || buf.starts_with(b"~!") {
+            buf.len().min(3)
+        } else {
+            buf.iter()
+                .position(|&b| b == b'\n')
+                .map_or(buf.len(), |n| n + 1)
+        };
+        let (command, rest) = buf.split_at(len);
+        buf = rest;
+        Some(command)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, sync::mpsc, thread};
+
+    fn config() -> NetworkConfig {
+        NetworkConfig {
+            reconnect_attempts: 2,
+            reconnect_delay: Duration::from_millis(10),
+            ..NetworkConfig::default()
+        }
+    }
+
+    #[test]
+    fn resends_label_after_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (dropped, wait_dropped) = mpsc::channel();
+        let server = thread::spawn(move || {
+            let (mut first, _) = listener.accept().unwrap();
+            let mut head = [0; 21];
+            first.read_exact(&mut head).unwrap();
+            assert_eq!(&head, b"CLS\r\n\x1b!?BAR 0,0,8,8\r\n");
+            // Reset instead of a graceful close, so the next write fails right away.
+            SockRef::from(&first)
+                .set_linger(Some(Duration::ZERO))
+                .unwrap();
+            drop(first);
+            dropped.send(()).unwrap();
+
+            let (mut second, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            second.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let mut transport = NetworkTransport::connect(&addr, config()).unwrap();
+        transport.write_all(b"CLS\r\n").unwrap();
+        transport.write_all(b"\x1b!?").unwrap();
+        transport.write_all(b"BAR 0,0,8,8\r\n").unwrap();
+        wait_dropped.recv().unwrap();
+        thread::sleep(Duration::from_millis(50));
+        transport.write_all(b"BAR 8,8,8,8\r\n").unwrap();
+        transport.write_all(b"PRINT 1,1\r\n").unwrap();
+        assert!(transport.label.is_empty());
+        drop(transport);
+
+        assert_eq!(
+            server.join().unwrap(),
+            b"CLS\r\nBAR 0,0,8,8\r\nBAR 8,8,8,8\r\nPRINT 1,1\r\n"
+        );
+    }
+
+    #[test]
+    fn keeps_only_the_unprinted_label() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            io::copy(&mut stream, &mut io::sink()).unwrap();
+        });
+
+        let mut transport = NetworkTransport::connect(
+            &addr,
+            NetworkConfig {
+                resend_limit: 24,
+                ..config()
+            },
+        )
+        .unwrap();
+        transport
+            .write_all(b"CLS\r\nBAR 0,0,8,8\r\nPRINT 1\r\nCLS\r\nBAR 1,1,8,8\r\n")
+            .unwrap();
+        assert_eq!(transport.label, b"CLS\r\nBAR 1,1,8,8\r\n");
+        transport.write_all(b"~!T\x1b!R").unwrap();
+        assert_eq!(transport.label, b"CLS\r\nBAR 1,1,8,8\r\n");
+        transport
+            .write_all(b"CLS\r\n\x1b!?BAR 1,1,8,8\r\n~!@")
+            .unwrap();
+        assert_eq!(transport.label, b"CLS\r\nBAR 1,1,8,8\r\n");
+
+        transport.write_all(b"BAR 2,2,8,8\r\n").unwrap();
+        assert!(transport.overflow && transport.label.is_empty());
+        transport.write_all(b"BAR 3,3,8,8\r\n").unwrap();
+        assert!(transport.label.is_empty());
+        transport.write_all(b"PRINT 1\r\n").unwrap();
+        assert!(!transport.overflow);
+
+        drop(transport);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn doesnt_resend_printed_labels_of_the_same_write() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let first = b"CLS\r\nBAR 0,0,8,8\r\nPRINT 1\r\n".to_vec();
+        // Larger than the socket buffers, so the write is still going on when the connection drops.
+        let mut second = b"CLS\r\nBITMAP 0,0,1024,8192,0,".to_vec();
+        second.resize(second.len() + (8 << 20), 0xff);
+        second.extend_from_slice(b"\r\nPRINT 1\r\n");
+        let job = [&first[..], &second[..]].concat();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = vec![0; first.len()];
+            stream.read_exact(&mut head).unwrap();
+            assert_eq!(head, first);
+            SockRef::from(&stream)
+                .set_linger(Some(Duration::ZERO))
+                .unwrap();
+            drop(stream);
+
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let mut transport = NetworkTransport::connect(
+            &addr,
+            NetworkConfig {
+                resend_limit: 16 << 20,
+                ..config()
+            },
+        )
+        .unwrap();
+        transport.write_all(&job).unwrap();
+        assert!(transport.label.is_empty());
+        drop(transport);
+
+        let received = server.join().unwrap();
+        assert!(received == second, "resent {} bytes", received.len());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{DataBits, Parity, SerialSettings, StopBits, TryClone};
+use log::debug;
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+/// Flow control used on the serial line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlowControl {
+    None,
+    /// XON/XOFF software flow control.
+    Software,
+    /// RTS/CTS hardware flow control.
+    Hardware,
+}
+
+/// Settings of the host side of the serial connection.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SerialConfig {
+    /// Line settings. They must match the printer's `SET COM1` configuration.
+    pub settings: SerialSettings,
+    pub flow_control: FlowControl,
+    /// Maximum time a single read or write may block.
+    pub timeout: Duration,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            settings: SerialSettings::default(),
+            flow_control: FlowControl::Hardware,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// RS-232 connection to a printer.
+pub struct SerialTransport {
+    path: String,
+    config: SerialConfig,
+    port: Box<dyn serialport::SerialPort>,
+}
+
+impl SerialTransport {
+    /// Opens the serial port at `path`, e.g. `/dev/ttyUSB0` or `COM3`.
+    pub fn open(path: &str, config: SerialConfig) -> io::Result<Self> {
+        let port = Self::open_port(path, &config)?;
+        Ok(Self {
+            path: path.to_owned(),
+            config,
+            port,
+        })
+    }
+
+    /// Switches the open port to new line settings, e.g. after the printer was reconfigured with `SET COM1`.
+    pub fn reconfigure(&mut self, settings: SerialSettings) -> io::Result<()> {
+        self.port.flush()?;
+        self.port
+            .set_baud_rate(settings.baud_rate.bits_per_second())?;
+        self.port.set_parity(settings.parity.into())?;
+        self.port.set_data_bits(settings.data_bits.into())?;
+        self.port.set_stop_bits(settings.stop_bits.into())?;
+        self.config.settings = settings;
+
+        debug!("reconfigured {} with {settings:?}", self.path);
+        Ok(())
+    }
+
+    /// Current settings of the host side of the connection.
+    pub fn config(&self) -> &SerialConfig {
+        &self.config
+    }
+
+    fn open_port(path: &str, config: &SerialConfig) -> io::Result<Box<dyn serialport::SerialPort>> {
+        let settings = &config.settings;
+        let port = serialport::new(path, settings.baud_rate.bits_per_second())
+            .parity(settings.parity.into())
+            .data_bits(settings.data_bits.into())
+            .stop_bits(settings.stop_bits.into())
+            .flow_control(config.flow_control.into())
+            .timeout(config.timeout)
+            .open()?;
+
+        debug!("opened {path} with {settings:?}, {:?}", config.flow_control);
+        Ok(port)
+    }
+}
+
+impl TryClone for SerialTransport {
+    fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            path: self.path.clone(),
+            config: self.config,
+            port: self.port.try_clone()?,
+        })
+    }
+}
+
+impl Write for SerialTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl Read for SerialTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl From<FlowControl> for serialport::FlowControl {
+    fn from(value: FlowControl) -> Self {
+        match value {
+            FlowControl::None => Self::None,
+            FlowControl::Software => Self::Software,
+            FlowControl::Hardware => Self::Hardware,
+        }
+    }
+}
+
+impl From<Parity> for serialport::Parity {
+    fn from(value: Parity) -> Self {
+        match value {
+            Parity::None => Self::None,
+            Parity::Even => Self::Even,
+            Parity::Odd => Self::Odd,
+        }
+    }
+}
+
+impl From<DataBits> for serialport::DataBits {
+    fn from(value: DataBits) -> Self {
+        match value {
+            DataBits::Seven => Self::Seven,
+            DataBits::Eight => Self::Eight,
+        }
+    }
+}
+
+impl From<StopBits> for serialport::StopBits {
+    fn from(value: StopBits) -> Self {
+        match value {
+            StopBits::One => Self::One,
+            StopBits::Two => Self::Two,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BaudRate, Printer};
+
+    #[test]
+    fn converts_line_settings() {
+        assert_eq!(
+            serialport::FlowControl::from(FlowControl::Software),
+            serialport::FlowControl::Software
+        );
+        assert_eq!(
+            serialport::FlowControl::from(FlowControl::Hardware),
+            serialport::FlowControl::Hardware
+        );
+        assert_eq!(
+            serialport::Parity::from(Parity::None),
+            serialport::Parity::None
+        );
+        assert_eq!(
+            serialport::Parity::from(Parity::Even),
+            serialport::Parity::Even
+        );
+        assert_eq!(
+            serialport::Parity::from(Parity::Odd),
+            serialport::Parity::Odd
+        );
+        assert_eq!(
+            serialport::DataBits::from(DataBits::Seven),
+            serialport::DataBits::Seven
+        );
+        assert_eq!(
+            serialport::DataBits::from(DataBits::Eight),
+            serialport::DataBits::Eight
+        );
+        assert_eq!(
+            serialport::StopBits::from(StopBits::One),
+            serialport::StopBits::One
+        );
+        assert_eq!(
+            serialport::StopBits::from(StopBits::Two),
+            serialport::StopBits::Two
+        );
+    }
+
+    #[test]
+    fn set_com1_command() {
+        let mut printer = Printer::unconfigured(Vec::new(), 203);
+        printer.set_com1(SerialSettings::default()).unwrap();
+        printer
+            .set_com1(SerialSettings {
+                baud_rate: BaudRate::B19200,
+                parity: Parity::Even,
+                data_bits: DataBits::Seven,
+                stop_bits: StopBits::Two,
+            })
+            .unwrap();
+        assert_eq!(
+            printer.bytes(),
+            b"SET COM1 96,N,8,1\r\nSET COM1 19,E,7,2\r\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn switches_line_settings_over_pty() {
+        use serialport::{SerialPort, TTYPort};
+
+        let (mut printer_side, host_side) = TTYPort::pair().unwrap();
+        let path = host_side.name().unwrap();
+        drop(host_side);
+
+        let config = SerialConfig {
+            flow_control: FlowControl::None,
+            ..SerialConfig::default()
+        };
+        let mut printer = Printer::unconfigured(SerialTransport::open(&path, config).unwrap(), 203);
+        let settings = SerialSettings {
+            baud_rate: BaudRate::B19200,
+            ..SerialSettings::default()
+        };
+        printer.switch_com1(settings).unwrap();
+
+        let expected = b"SET COM1 19,N,8,1\r\n";
+        let mut received = vec![0; expected.len()];
+        printer_side.set_timeout(Duration::from_secs(1)).unwrap();
+        printer_side.read_exact(&mut received).unwrap();
+        assert_eq!(received, expected);
+        assert_eq!(printer.get_ref().config().settings, settings);
+        assert_eq!(printer.get_ref().port.baud_rate().unwrap(), 19200);
+    }
+}